@@ -1,2 +1,3 @@
 pub mod update_monitor;
-pub mod notify_events;
\ No newline at end of file
+pub mod notify_events;
+pub mod set_swap_route;
\ No newline at end of file
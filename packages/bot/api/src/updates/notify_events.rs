@@ -1,9 +1,33 @@
+use std::collections::BTreeMap;
 use candid::CandidType;
+use icrc_ledger_types::icrc::generic_value::Value;
+use monitor_api::{types::job::JobDestination, updates::add_job::JobId};
 use serde::{Deserialize, Serialize};
 
+pub const NOTIFY_EVENTS_VERSION: u32 = 2;
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct JobEvent {
+    pub job_id: JobId,
+    // sequential per job, so a redelivered event can be detected
+    pub index: u64,
+    // the event as returned by the source, before rendering
+    pub fields: BTreeMap<String, Value>,
+    pub text: String,
+}
+
 #[derive(Serialize, Deserialize, CandidType)]
 pub struct NotifiyEventsArgs{
+    // None for monitors still sending the version 1 payload
+    pub version: Option<u32>,
+    // version 1: pre-rendered messages. To be removed once all monitors are upgraded
     pub messages: Vec<String>,
+    pub events: Option<Vec<JobEvent>>,
+    // posted before and after the events of a digest
+    pub header: Option<String>,
+    pub footer: Option<String>,
+    // None posts to the monitor's chat
+    pub destination: Option<JobDestination>,
 }
 
-pub type NotifiyEventsResponse = Result<(), String>;
\ No newline at end of file
+pub type NotifiyEventsResponse = Result<(), String>;
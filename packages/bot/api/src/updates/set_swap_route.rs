@@ -0,0 +1,14 @@
+use candid::{CandidType, Principal};
+use serde::Deserialize;
+
+#[derive(Deserialize, CandidType)]
+pub struct SetSwapRouteArgs {
+    // token symbol, ie: CHAT
+    pub token: String,
+    // ICPSwap pool converting the token to ICP, None to remove the route
+    pub pool_canister_id: Option<Principal>,
+    // true if the token is the pool's token0
+    pub zero_for_one: bool,
+}
+
+pub type SetSwapRouteResponse = Result<(), String>;
@@ -2,3 +2,6 @@ use crate::services::monitor::MIN_MONITOR_CYCLES;
 
 pub const DEPLOY_CANISTER_CYCLES: u128 = 500_000_000_000;
 pub const DEPLOY_MONITOR_CYCLES: u128 = DEPLOY_CANISTER_CYCLES + MIN_MONITOR_CYCLES;
+
+pub const NNS_GOVERNANCE_CANISTER_ID: &str = "rrkah-fqaaa-aaaaa-aaaaq-cai";
+pub const ICP_INDEX_CANISTER_ID: &str = "qhbym-qaaaa-aaaaa-aaafq-cai";
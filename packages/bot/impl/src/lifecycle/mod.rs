@@ -3,7 +3,9 @@ use ic_ledger_types::DEFAULT_SUBACCOUNT;
 use crate::{
     services::{
         fund::fund::{FundCanisterConfig, FundService}, 
-        monitor::MonitorService
+        monitor::MonitorService, 
+        throttle::ThrottleService,
+        wallet::wallet::WalletService
     }, 
     state::{self, State}
 };
@@ -30,9 +32,10 @@ pub(crate) fn setup(
         service.start(
             vec![FundCanisterConfig {
                 canister_id: ic_cdk::id(),
-                from_subaccount: DEFAULT_SUBACCOUNT,
+                from_subaccounts: vec![DEFAULT_SUBACCOUNT],
                 min_cycles:  5_000_000_000_000,
                 fund_cycles: 1_000_000_000_000,
+                hooks: None,
             }],     
             10 * 60 // every 10 minutes
         );
@@ -40,6 +43,12 @@ pub(crate) fn setup(
 
     MonitorService::start();
 
+    // retry the credits of the users' wallets that failed
+    WalletService::start();
+
+    // post the events still waiting for the rate limit
+    ThrottleService::start_if_required();
+
     // update all deployed monitors if the wasm changed
     ic_cdk_timers::set_timer(
         Duration::ZERO, 
@@ -7,6 +7,10 @@ const UPGRADES: MemoryId            = MemoryId::new(0);
 const MONITORS: MemoryId            = MemoryId::new(1);
 const CAN_TO_MON_ID: MemoryId       = MemoryId::new(2);
 const USERS: MemoryId               = MemoryId::new(3);
+const THROTTLES: MemoryId           = MemoryId::new(4);
+const PERMISSIONS: MemoryId         = MemoryId::new(5);
+const SWAP_ROUTES: MemoryId         = MemoryId::new(6);
+const PENDING_TOP_UPS: MemoryId     = MemoryId::new(7);
 
 pub type Memory = VirtualMemory<DefaultMemoryImpl>;
 
@@ -34,3 +38,19 @@ pub fn get_can_to_mon_id_memory() -> Memory {
 pub fn get_users_memory() -> Memory {
     get_memory(USERS)
 }
+
+pub fn get_throttles_memory() -> Memory {
+    get_memory(THROTTLES)
+}
+
+pub fn get_permissions_memory() -> Memory {
+    get_memory(PERMISSIONS)
+}
+
+pub fn get_swap_routes_memory() -> Memory {
+    get_memory(SWAP_ROUTES)
+}
+
+pub fn get_pending_top_ups_memory() -> Memory {
+    get_memory(PENDING_TOP_UPS)
+}
\ No newline at end of file
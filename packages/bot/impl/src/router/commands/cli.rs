@@ -2,8 +2,21 @@ use std::{collections::HashSet, sync::LazyLock};
 use async_trait::async_trait;
 use candid::Principal;
 use clap::Parser;
-use ic_ledger_types::{AccountIdentifier, DEFAULT_FEE, DEFAULT_SUBACCOUNT};
-use monitor_api::updates::add_job::JobId;
+use ic_ledger_types::{AccountIdentifier, DEFAULT_FEE, DEFAULT_SUBACCOUNT, MAINNET_LEDGER_CANISTER_ID};
+use monitor_api::{
+    types::{
+        job::{
+            JobCanister, JobDelivery, JobDestination, JobIcpLedger, JobIcrc3Ledger, 
+            JobNnsProposals, JobSnsProposals, JobType
+        }, 
+        nns::{status_id, topic_id, NNS_PROPOSAL_STATUSES, NNS_TOPICS}
+    }, 
+    updates::{
+        add_job::{AddJobArgs, JobId}, 
+        seek_job::SeekPosition, 
+        update_job::UpdateJobArgs
+    }
+};
 use oc_bots_sdk::{
     api::{
         command::{
@@ -21,22 +34,35 @@ use oc_bots_sdk::{
 };
 use oc_bots_sdk_canister::CanisterRuntime;
 use crate::{
-    consts::DEPLOY_MONITOR_CYCLES, 
+    consts::{DEPLOY_MONITOR_CYCLES, NNS_GOVERNANCE_CANISTER_ID}, 
     services::{
         monitor::MonitorService, 
+        permission::PermissionService, 
+        swap::SwapService, 
+        throttle::ThrottleService, 
         wallet::wallet::WalletService
     }, 
     state, 
     storage::user::UserStorage, 
     types::{
-        cli::{Cli, Commands, CreateSubcommand, Job, Wallet}, 
+        cli::{
+            Cli, Commands, Config, CreateSubcommand, DeliveryOptions, 
+            Funding, FundingChanges, Job, JobChanges, Monitor, Outbox, Wallet
+        }, 
+        funding::FundingPolicy, 
+        permission::{Action, Role}, 
+        throttle::{RateLimit, DEFAULT_MAX_QUEUE}, 
+        token::{Token, TOKENS}, 
         user::{UserId, UserTransaction}
-    }, utils::cmc::Cmc
+    }, 
+    utils::{chat::{destination_to_chat, has_text_api_key}, cmc::Cmc, decimal::parse_decimal}
 };
 
 static DEFINITION: LazyLock<BotCommandDefinition> = LazyLock::new(EventsMonCli::definition);
 
 const LOG_ITEMS_PER_PAGE: usize = 8;
+// cycles are given in trillions
+const TCYCLES_DECIMALS: u32 = 12;
 
 pub struct EventsMonCli;
 
@@ -83,75 +109,170 @@ impl CommandHandler<CanisterRuntime> for EventsMonCli {
 
         let res = match Cli::try_parse_from(args) {
             Ok(cli) => {
-                match cli.command {
-                    Commands::Deploy => {
-                        Self::deploy_monitor(
-                            user_id,
-                            chat,
-                            &client
-                        ).await
-                    },
-                    Commands::Status => {
-                        Self::monitor_status(
-                            chat,
-                            &client
-                        ).await
-                    },
-                    Commands::Job (command) => {
-                        match command {
-                            Job::Create ( subcommand ) => {
-                                match subcommand {
-                                    CreateSubcommand::Canister { 
-                                        canister_id, method_name, output_template, 
-                                        batch_size, interval } => {
-                                        Self::create_canister_job(
-                                            canister_id, method_name, interval, 
-                                            batch_size, output_template, chat, &client
-                                        ).await
+                let allowed = Self::required_action(&cli.command, user_id)
+                    .map_or(Ok(()), |action| PermissionService::authorize(user_id, chat, action));
+
+                if let Err(err) = allowed {
+                    Err(err)
+                }
+                else {
+                    match cli.command {
+                        Commands::Deploy { token } => {
+                            Self::deploy_monitor(
+                                user_id,
+                                token,
+                                chat,
+                                &client
+                            ).await
+                        },
+                        Commands::Status => {
+                            Self::monitor_status(
+                                chat,
+                                &client
+                            ).await
+                        },
+                        Commands::Job (command) => {
+                            match command {
+                                Job::Create ( subcommand ) => {
+                                    Self::create_job(user_id, subcommand, chat, &client)
+                                        .await
+                                },
+                                Job::List { page } => {
+                                    Self::list_jobs(page.max(1) - 1, chat, &client)
+                                        .await
+                                },
+                                Job::Show { id } => {
+                                    Self::show_job(id, chat, &client)
+                                        .await
+                                },
+                                Job::Logs { id, page } => {
+                                    Self::job_logs(id, page.max(1) - 1, chat, &client)
+                                        .await
+                                },
+                                Job::Seek { id, position } => {
+                                    Self::seek_job(id, position, chat, &client)
+                                        .await
+                                },
+                                Job::Start { id } => {
+                                    Self::start_job(id, chat, &client)
+                                        .await
+                                },
+                                Job::Stop { id } => {
+                                    Self::stop_job(id, chat, &client)
+                                        .await
+                                },
+                                Job::Edit { id, changes } => {
+                                    Self::edit_job(user_id, id, changes, chat, &client)
+                                        .await
+                                },
+                                Job::Delete { id } => {
+                                    Self::delete_job(id, chat, &client)
+                                        .await
+                                },
+                                Job::Outbox(command) => {
+                                    match command {
+                                        Outbox::List { page, dead } => {
+                                            Self::list_outbox(page.max(1) - 1, dead, chat, &client)
+                                                .await
+                                        },
+                                        Outbox::Flush => {
+                                            Self::flush_outbox(chat, &client)
+                                                .await
+                                        },
+                                        Outbox::Drop { id, dead } => {
+                                            Self::drop_outbox(id, dead, chat, &client)
+                                                .await
+                                        },
                                     }
-                                }
-                            },
-                            Job::List { page } => {
-                                Self::list_jobs(page.max(1) - 1, chat, &client)
-                                    .await
-                            },
-                            Job::Start { id } => {
-                                Self::start_job(id, chat, &client)
-                                    .await
-                            },
-                            Job::Stop { id } => {
-                                Self::stop_job(id, chat, &client)
-                                    .await
-                            },
-                            Job::Delete { id } => {
-                                Self::delete_job(id, chat, &client)
-                                    .await
-                            },
-                        }
-                    },
-                    Commands::Wallet (command) => {
-                        match command {
-                            Wallet::Balance => {
-                                Self::wallet_balance(user_id, &client)
-                                    .await
-                            },
-                            Wallet::Address => {
-                                Self::wallet_address(user_id, &client)
-                                    .await
-                            },
-                            Wallet::Withdraw { to, amount } => {
-                                Self::wallet_withdraw(user_id, to, amount, &client)
-                                    .await
-                            },
-                            Wallet::Logs { page } => {
-                                Self::wallet_logs(
-                                    user_id,
-                                    page.max(1) - 1,
-                                    &client
-                                )
-                            },
-                        }
-                    },
+                                },
+                            }
+                        },
+                        Commands::Wallet (command) => {
+                            match command {
+                                Wallet::Balance => {
+                                    Self::wallet_balance(user_id, &client)
+                                        .await
+                                },
+                                Wallet::Address => {
+                                    Self::wallet_address(user_id, &client)
+                                        .await
+                                },
+                                Wallet::Withdraw { to, amount, token } => {
+                                    Self::wallet_withdraw(user_id, token, to, amount, &client)
+                                        .await
+                                },
+                                Wallet::Swap { amount, token } => {
+                                    Self::wallet_swap(user_id, token, amount, &client)
+                                        .await
+                                },
+                                Wallet::Logs { page } => {
+                                    Self::wallet_logs(
+                                        user_id,
+                                        page.max(1) - 1,
+                                        &client
+                                    ).await
+                                },
+                            }
+                        },
+                        Commands::Config (command) => {
+                            match command {
+                                Config::Show => {
+                                    Self::show_config(chat, &client)
+                                },
+                                Config::RateLimit { per_minute, burst, queue } => {
+                                    Self::set_rate_limit(per_minute, burst, queue, chat, &client)
+                                },
+                                Config::Role { user_id: to, role } => {
+                                    Self::grant_role(user_id, to, role, chat, &client)
+                                },
+                                Config::Permission { action, role } => {
+                                    Self::set_permission(user_id, action, role, chat, &client)
+                                },
+                            }
+                        },
+                        Commands::Monitor (command) => {
+                            match command {
+                                Monitor::Stop => {
+                                    Self::stop_monitor(chat, &client)
+                                        .await
+                                },
+                                Monitor::Start => {
+                                    Self::start_monitor(chat, &client)
+                                        .await
+                                },
+                                Monitor::Upgrade => {
+                                    Self::upgrade_monitor(chat, &client)
+                                        .await
+                                },
+                                Monitor::Delete => {
+                                    Self::delete_monitor(user_id, chat, &client)
+                                        .await
+                                },
+                                Monitor::TransferOwner { user_id: to } => {
+                                    Self::transfer_owner(user_id, to, chat, &client)
+                                },
+                                Monitor::FundFrom { user_id } => {
+                                    Self::fund_from(user_id, chat, &client)
+                                },
+                                Monitor::Accept => {
+                                    Self::accept(user_id, chat, &client)
+                                },
+                                Monitor::RemoveFunder { user_id: funder } => {
+                                    Self::remove_funder(user_id, funder, chat, &client)
+                                },
+                                Monitor::Funding(command) => {
+                                    match command {
+                                        Funding::Show => {
+                                            Self::show_funding(chat, &client)
+                                        },
+                                        Funding::Set { changes } => {
+                                            Self::set_funding(user_id, changes, chat, &client)
+                                        },
+                                    }
+                                },
+                            }
+                        },
+                    }
                 }
             },
             Err(err) => {
@@ -186,8 +307,51 @@ impl CommandHandler<CanisterRuntime> for EventsMonCli {
 }
 
 impl EventsMonCli {
+    // read-only commands and the user's own wallet need no permission
+    fn required_action(
+        command: &Commands,
+        user_id: UserId
+    ) -> Option<Action> {
+        match command {
+            Commands::Deploy { .. } => Some(Action::Deploy),
+            Commands::Status => None,
+            Commands::Job(command) => match command {
+                Job::List { .. } | Job::Show { .. } | Job::Logs { .. } => None,
+                Job::Outbox(Outbox::List { .. }) => None,
+                Job::Start { .. } | Job::Stop { .. } => Some(Action::StartStopJobs),
+                _ => Some(Action::EditJobs),
+            },
+            Commands::Wallet(_) => None,
+            Commands::Config(command) => match command {
+                Config::Show => None,
+                _ => Some(Action::Config),
+            },
+            // ownership is also checked by the monitor service
+            Commands::Monitor(command) => match command {
+                Monitor::Stop | Monitor::Start => Some(Action::StartStopJobs),
+                Monitor::Upgrade | Monitor::Delete => Some(Action::Deploy),
+                Monitor::TransferOwner { .. } => Some(Action::Deploy),
+                Monitor::FundFrom { .. } => Some(Action::Config),
+                // funders can always stop funding the monitor
+                Monitor::RemoveFunder { user_id: funder } => {
+                    if Principal::from_text(funder).ok() == Some(user_id) {
+                        None
+                    }
+                    else {
+                        Some(Action::Config)
+                    }
+                },
+                // only the invited user can accept, checked by the monitor service
+                Monitor::Accept => None,
+                Monitor::Funding(Funding::Show) => None,
+                Monitor::Funding(Funding::Set { .. }) => Some(Action::Config),
+            },
+        }
+    }
+
     async fn deploy_monitor(
         user_id: UserId,
+        token: Token,
         chat: Chat,
         client: &Client<CanisterRuntime, BotCommandContext>
     ) -> Result<SuccessResult, String> {
@@ -199,7 +363,29 @@ impl EventsMonCli {
         );
 
         let cost = Cmc::cycles_to_icp(DEPLOY_MONITOR_CYCLES).await?;
-        let balance = WalletService::balance_of(user_id).await? as u128;
+        let mut balance = WalletService::balance_of(user_id).await? as u128;
+
+        // pay the missing ICP with the token
+        if balance < cost && token != Token::Icp {
+            let amount = SwapService::estimate_input(token, (cost - balance) as u64).await?;
+            let token_balance = WalletService::token_balance_of(user_id, token).await?;
+            if token_balance < amount {
+                return Err(
+                    format!(
+                        "Your EventMon wallet balance of **{:.8}** {} is too low to cover the current monitor deployment cost of about **{:.8}** {}  \nPlease transfer enough {} to this account: **{}**", 
+                        token.from_units(token_balance),
+                        token,
+                        token.from_units(amount),
+                        token,
+                        token,
+                        WalletService::token_account(user_id)
+                    )
+                );
+            }
+
+            balance += SwapService::swap_to_icp(user_id, token, amount).await? as u128;
+        }
+
         if balance < cost {
             let acc_id = WalletService::address_of(user_id);
             return Err(
@@ -212,17 +398,26 @@ impl EventsMonCli {
             );
         }
         
-        if let Err(err) = WalletService::transfer(
+        match WalletService::transfer(
             user_id.into(), 
             AccountIdentifier::new(&ic_cdk::id(), &DEFAULT_SUBACCOUNT), 
             cost as _
         ).await {
-            let err = format!(
-                "Failed paying the deployment cost: {}.", 
-                err
-            );
-            ic_cdk::println!("error: {}", err);
-            return Err(err);
+            Ok((block_num, _)) => {
+                WalletService::add_tx(user_id, UserTransaction::DeploymentFee { 
+                    amount: cost as _, 
+                    block_num, 
+                    timestamp: (ic_cdk::api::time() / 1_000_000_000) as _,
+                });
+            },
+            Err(err) => {
+                let err = format!(
+                    "Failed paying the deployment cost: {}.", 
+                    err
+                );
+                ic_cdk::println!("error: {}", err);
+                return Err(err);
+            }
         };
         
         let canister_id = match MonitorService::deploy(
@@ -233,17 +428,27 @@ impl EventsMonCli {
             Err(err) => {
                 ic_cdk::println!("error: monitor deployment failed: {}", err);
 
-                if let Err(err) = WalletService::transfer(
+                let refund = cost as u64 + DEFAULT_FEE.e8s();
+                match WalletService::transfer(
                     None, 
                     AccountIdentifier::new(&ic_cdk::id(), &user_id.into()), 
-                    cost as u64 + DEFAULT_FEE.e8s()
+                    refund
                 ).await {
-                    ic_cdk::println!(
-                        "error: could not return deployment cost {} to user {}: {}", 
-                        cost, 
-                        user_id.to_text(), 
-                        err
-                    );
+                    Ok((block_num, _)) => {
+                        WalletService::add_tx(user_id, UserTransaction::DeploymentRefund { 
+                            amount: refund, 
+                            block_num, 
+                            timestamp: (ic_cdk::api::time() / 1_000_000_000) as _,
+                        });
+                    },
+                    Err(err) => {
+                        ic_cdk::println!(
+                            "error: could not return deployment cost {} to user {}: {}", 
+                            cost, 
+                            user_id.to_text(), 
+                            err
+                        );
+                    }
                 };
 
                 return Err(err);
@@ -256,20 +461,116 @@ impl EventsMonCli {
         ).with_block_level_markdown(true).build().into())
     }
 
-    async fn create_canister_job(
-        canister_id: String, 
-        method_name: String, 
-        interval: u32,
-        batch_size: u32,
-        output_template: String, 
+    async fn create_job(
+        user_id: UserId,
+        subcommand: CreateSubcommand,
         chat: Chat,
         client: &Client<CanisterRuntime, BotCommandContext>
     ) -> Result<SuccessResult, String> {
 
-        let canister_id = Principal::from_text(canister_id).unwrap();
+        let (ty, interval, output_template, options) = match subcommand {
+            CreateSubcommand::Canister { 
+                canister_id, method_name, interval, output_template, options } => {
+                (
+                    JobType::Canister(JobCanister { 
+                        canister_id: Self::parse_canister_id(&canister_id)?, 
+                        method_name 
+                    }),
+                    interval, output_template, options
+                )
+            },
+            CreateSubcommand::Icrc3 { 
+                canister_id, interval, output_template, options } => {
+                (
+                    JobType::Icrc3Ledger(JobIcrc3Ledger { 
+                        canister_id: Self::parse_canister_id(&canister_id)?
+                    }),
+                    interval, output_template, options
+                )
+            },
+            CreateSubcommand::Icp { 
+                interval, output_template, accounts, ledger, options } => {
+                for acc in &accounts {
+                    AccountIdentifier::from_hex(acc)
+                        .map_err(|e| format!("Invalid account id {}: {}", acc, e))?;
+                }
+                (
+                    JobType::IcpLedger(JobIcpLedger { 
+                        canister_id: match ledger {
+                            Some(ledger) => Self::parse_canister_id(&ledger)?,
+                            None => MAINNET_LEDGER_CANISTER_ID,
+                        },
+                        accounts
+                    }),
+                    interval, output_template, options
+                )
+            },
+            CreateSubcommand::Sns { 
+                governance_canister_id, interval, output_template, options } => {
+                (
+                    JobType::SnsProposals(JobSnsProposals { 
+                        governance_canister_id: Self::parse_canister_id(&governance_canister_id)?
+                    }),
+                    interval, output_template, options
+                )
+            },
+            CreateSubcommand::Nns { 
+                interval, output_template, topics, excluded_statuses, governance, options } => {
+                let topics = topics.iter()
+                    .map(|name| topic_id(name).ok_or_else(|| format!(
+                        "Invalid topic {}. Valid topics: {}", 
+                        name, 
+                        NNS_TOPICS.iter().map(|(_, n)| *n).collect::<Vec<_>>().join(", ")
+                    )))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let excluded_statuses = excluded_statuses.iter()
+                    .map(|name| status_id(name).ok_or_else(|| format!(
+                        "Invalid status {}. Valid statuses: {}", 
+                        name, 
+                        NNS_PROPOSAL_STATUSES.iter().map(|(_, n)| *n).collect::<Vec<_>>().join(", ")
+                    )))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (
+                    JobType::NnsProposals(JobNnsProposals { 
+                        governance_canister_id: Self::parse_canister_id(
+                            governance.as_deref().unwrap_or(NNS_GOVERNANCE_CANISTER_ID)
+                        )?,
+                        topics,
+                        excluded_statuses
+                    }),
+                    interval, output_template, options
+                )
+            },
+        };
+
+        let offset = match options.from.to_lowercase().as_str() {
+            "latest" => None,
+            "start" => Some(0),
+            from => Some(
+                from.parse().map_err(|_| format!("Invalid start position: {}", options.from))?
+            ),
+        };
+
+        let destination = match &options.to {
+            Some(to) => Self::parse_destination(to, user_id, chat)?,
+            None => None,
+        };
+
+        let delivery = Self::parse_delivery(options.delivery)?;
 
-        let job_id = MonitorService::add_canister_job(
-            chat.into(), canister_id, method_name, interval, batch_size, output_template
+        let job_id = MonitorService::add_job(
+            chat.into(), 
+            AddJobArgs {
+                ty, 
+                interval, 
+                batch_size: options.batch_size, 
+                output_template,
+                filter: options.filter,
+                max_failures: options.max_failures,
+                destination,
+                delivery,
+                offset
+            }
         ).await?;
 
         Ok(
@@ -282,6 +583,234 @@ impl EventsMonCli {
         )
     }
 
+    // immediate, digest:<minutes> or threshold:<events>
+    fn parse_delivery(
+        options: DeliveryOptions
+    ) -> Result<Option<JobDelivery>, String> {
+        let DeliveryOptions { delivery, header, footer } = options;
+        let delivery = match delivery {
+            Some(delivery) => delivery,
+            None if header.is_some() || footer.is_some() => {
+                return Err("--header and --footer require --delivery digest:<minutes>".to_string());
+            },
+            None => return Ok(None),
+        };
+
+        let (mode, value) = match delivery.split_once(':') {
+            Some((mode, value)) => (mode.to_lowercase(), Some(value)),
+            None => (delivery.to_lowercase(), None),
+        };
+        let parse_value = |what: &str| -> Result<u32, String> {
+            value
+                .ok_or_else(|| format!("Missing {}, ie: {}:10", what, mode))?
+                .parse()
+                .map_err(|_| format!("Invalid {}: {}", what, value.unwrap_or_default()))
+        };
+
+        if mode != "digest" && (header.is_some() || footer.is_some()) {
+            return Err("--header and --footer can only be used with --delivery digest:<minutes>".to_string());
+        }
+
+        let delivery = match mode.as_str() {
+            "immediate" => JobDelivery::Immediate,
+            "digest" => JobDelivery::Digest {
+                every: parse_value("number of minutes")?,
+                header,
+                footer,
+            },
+            "threshold" => JobDelivery::Threshold {
+                events: parse_value("number of events")?,
+            },
+            _ => return Err(format!("Unknown delivery mode: {}. Use immediate, digest:<minutes> or threshold:<events>", mode)),
+        };
+
+        Ok(Some(delivery))
+    }
+
+    // "here" means the chat where the command was used
+    fn parse_destination(
+        to: &str,
+        user_id: UserId,
+        chat: Chat
+    ) -> Result<Option<JobDestination>, String> {
+        let destination = if to.eq_ignore_ascii_case("here") {
+            return Ok(None);
+        }
+        else if let Some((community_id, channel_id)) = to.split_once('/') {
+            JobDestination::Channel(
+                Self::parse_canister_id(community_id)?,
+                channel_id.parse().map_err(|_| format!("Invalid channel id: {}", channel_id))?
+            )
+        }
+        else if let Ok(channel_id) = to.parse() {
+            match chat {
+                Chat::Channel(community_id, _) => JobDestination::Channel(community_id, channel_id),
+                _ => return Err("A channel id alone can only be used inside a community".to_string()),
+            }
+        }
+        else {
+            JobDestination::Group(Self::parse_canister_id(to)?)
+        };
+
+        let to_chat = destination_to_chat(&destination);
+        if !has_text_api_key(to_chat) {
+            return Err(format!(
+                "You must first register an API key for the {} with the \"send text message\" permission", 
+                destination
+            ));
+        }
+
+        // posting to another chat requires the same permission there
+        if to_chat != chat && PermissionService::authorize(user_id, to_chat, Action::EditJobs).is_err() {
+            return Err(format!(
+                "You can only post to the {} if you own its monitor or an admin there grants you a role allowed to {} with `/eventmon config role`", 
+                destination, Action::EditJobs
+            ));
+        }
+
+        Ok(Some(destination))
+    }
+
+    fn parse_canister_id(
+        canister_id: &str
+    ) -> Result<Principal, String> {
+        Principal::from_text(canister_id)
+            .map_err(|e| format!("Invalid canister id {}: {}", canister_id, e))
+    }
+
+    async fn show_job(
+        job_id: JobId, 
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let job = MonitorService::get_job(chat.into(), job_id).await?;
+
+        let now = ic_cdk::api::time() / 1_000_000_000;
+        let last_run = job.last_run_at
+            .map(|ts| format!("{}s ago", now.saturating_sub(ts / 1_000_000_000)))
+            .unwrap_or_else(|| "never".to_string());
+        let next_due = job.next_due_at
+            .map(|ts| format!("in {}s", (ts / 1_000_000_000).saturating_sub(now)))
+            .unwrap_or_else(|| "not scheduled".to_string());
+
+        let text = format!(
+            "**Job ({})**:  \n- interval: {}s  \n- batch size: {}  \n- state: {}  \n- type: {}  \n- position: {}  \n- last run: {}  \n- next run: {}  \n- failures: {}/{}  \n- last error: {}  \n- delivery: {}{}  \n- template: ```{}```{}{}", 
+            job.id, 
+            job.interval, 
+            job.batch_size, 
+            job.state, 
+            job.ty, 
+            job.offset, 
+            last_run, 
+            next_due, 
+            job.failures, 
+            job.max_failures, 
+            job.last_error.unwrap_or_else(|| "none".to_string()), 
+            job.delivery,
+            if job.pending > 0 { format!(" ({} events pending)", job.pending) } else { String::new() },
+            job.output_template,
+            job.filter
+                .map(|f| format!("  \n- filter: ```{}```", f))
+                .unwrap_or_default(),
+            job.destination
+                .map(|d| format!("  \n- destination: {}", d))
+                .unwrap_or_default()
+        );
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(text),
+                client.context().message_id().unwrap(),
+            )
+            .with_block_level_markdown(true)
+            .build()
+            .into()
+        )
+    }
+
+    async fn job_logs(
+        job_id: JobId, 
+        page_num: usize,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let res = MonitorService::get_job_runs(
+            chat.into(), 
+            job_id, 
+            (page_num * LOG_ITEMS_PER_PAGE) as u32, 
+            LOG_ITEMS_PER_PAGE as u32
+        ).await?;
+
+        let logs = res.runs.iter()
+            .map(|run| format!(
+                "Run at timestamp({}): fetched({}) posted({}) cycles({}){}", 
+                run.timestamp, 
+                run.fetched, 
+                run.posted, 
+                run.cycles, 
+                run.error.as_ref()
+                    .map(|err| format!(" error({})", err))
+                    .unwrap_or_default()
+            ))
+            .collect::<Vec<_>>()
+            .join("  \n");
+
+        let num_pages = (res.total as usize + LOG_ITEMS_PER_PAGE-1) / LOG_ITEMS_PER_PAGE;
+        let page_num = (1+page_num).min(num_pages);
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::Text(
+                    format!("{}  \n  \nPage {}/{}",
+                        if logs.len() > 0 {
+                            logs
+                        } 
+                        else {
+                            "No runs found".to_string()
+                        },
+                        page_num,
+                        num_pages
+                    ).into()
+                ), 
+                client.context().message_id().unwrap()
+            ).with_block_level_markdown(true)
+                .build()
+                .into()
+        )
+    }
+
+    async fn seek_job(
+        job_id: JobId, 
+        position: String,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let position = if position.eq_ignore_ascii_case("latest") {
+            SeekPosition::Latest
+        }
+        else if let Some(n) = position.strip_prefix('-') {
+            SeekPosition::Back(
+                n.parse().map_err(|_| format!("Invalid position: {}", position))?
+            )
+        }
+        else {
+            SeekPosition::Absolute(
+                position.parse().map_err(|_| format!("Invalid position: {}", position))?
+            )
+        };
+
+        let offset = MonitorService::seek_job(chat.into(), job_id, position).await?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!("Job {} moved to position {}!", job_id, offset)),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
     async fn start_job(
         job_id: JobId, 
         chat: Chat,
@@ -316,6 +845,48 @@ impl EventsMonCli {
         )
     }
 
+    async fn edit_job(
+        user_id: UserId,
+        job_id: JobId, 
+        changes: JobChanges,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let JobChanges { interval, template, batch_size, filter, max_failures, to, delivery } = changes;
+        if interval.is_none() && template.is_none() && batch_size.is_none() && 
+            filter.is_none() && max_failures.is_none() && to.is_none() && delivery.delivery.is_none() {
+            return Err("Nothing to edit. Use --interval, --template, --batch-size, --filter, --max-failures, --to or --delivery".to_string());
+        }
+
+        let destination = match &to {
+            Some(to) => Some(Self::parse_destination(to, user_id, chat)?),
+            None => None,
+        };
+
+        MonitorService::update_job(
+            chat.into(), 
+            UpdateJobArgs {
+                job_id, 
+                interval, 
+                batch_size, 
+                output_template: template, 
+                filter,
+                max_failures,
+                destination,
+                delivery: Self::parse_delivery(delivery)?
+            }
+        ).await?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!("Job {} updated!", job_id)),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
     async fn delete_job(
         job_id: JobId, 
         chat: Chat,
@@ -345,12 +916,18 @@ impl EventsMonCli {
 
         let text = list.iter()
             .map(|j| format!(
-                "**Job ({})**:  \n- interval: {}s  \n- state: {}  \n- type: {}  \n- template: ```{}```", 
+                "**Job ({})**:  \n- interval: {}s  \n- state: {}  \n- type: {}  \n- template: ```{}```{}{}", 
                 j.id, 
                 j.interval, 
                 j.state, 
                 j.ty, 
-                j.output_template
+                j.output_template,
+                j.filter.as_ref()
+                    .map(|f| format!("  \n- filter: ```{}```", f))
+                    .unwrap_or_default(),
+                j.destination.as_ref()
+                    .map(|d| format!("  \n- destination: {}", d))
+                    .unwrap_or_default()
             ))
             .collect::<Vec<_>>()
             .join("  \n  \n---  \n");
@@ -366,6 +943,86 @@ impl EventsMonCli {
         )
     }
 
+    async fn list_outbox(
+        page: u32,
+        dead: bool,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+
+        let list = MonitorService::list_outbox(
+            chat.into(), page, dead
+        ).await?;
+
+        let text = if list.len() > 0 {
+            list.iter()
+                .map(|i| format!(
+                    "**Item ({})**:  \n- job: {}  \n- messages: {}  \n- attempts: {}  \n- last error: {}", 
+                    i.id, 
+                    i.job_id, 
+                    i.messages.len(), 
+                    i.attempts, 
+                    i.last_error.clone().unwrap_or_else(|| "none".to_string())
+                ))
+                .collect::<Vec<_>>()
+                .join("  \n  \n---  \n")
+        }
+        else {
+            "The outbox is empty".to_string()
+        };
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(text),
+                client.context().message_id().unwrap(),
+            )
+            .with_block_level_markdown(true)
+            .build()
+            .into()
+        )
+    }
+
+    async fn flush_outbox(
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let count = MonitorService::flush_outbox(chat.into()).await?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!("{} item(s) queued for delivery!", count)),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
+    async fn drop_outbox(
+        id: String,
+        dead: bool,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let id = if id.eq_ignore_ascii_case("all") {
+            None
+        }
+        else {
+            Some(id.parse().map_err(|_| format!("Invalid item id: {}", id))?)
+        };
+
+        let count = MonitorService::drop_outbox(chat.into(), id, dead).await?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!("{} item(s) dropped!", count)),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
     async fn monitor_status(
         chat: Chat,
         client: &Client<CanisterRuntime, BotCommandContext>
@@ -375,12 +1032,23 @@ impl EventsMonCli {
             chat.into()
         ).await?;
 
+        let funders = status.funders.iter()
+            .map(|user_id| user_id.to_text())
+            .collect::<Vec<_>>();
+        let pending = status.pending_owner.iter()
+            .map(|user_id| format!("{} (ownership)", user_id))
+            .chain(status.pending_funders.iter().map(|user_id| format!("{} (funding)", user_id)))
+            .collect::<Vec<_>>();
+
         let text = format!(
-            "- state: {}  \n- module hash: {}  \n- memory size: {}  \n- cycles available: **{:3.8}**",
+            "- state: {}  \n- module hash: {}  \n- memory size: {}  \n- cycles available: **{:3.8}**  \n- owner: {}  \n- co-funders: {}  \n- waiting acceptance: {}",
             status.status,
             status.module_hash,
             status.memory_size,
-            (status.cycles as f32) / 100000000.0
+            (status.cycles as f32) / 100000000.0,
+            status.owner,
+            if funders.is_empty() { "none".to_string() } else { funders.join(", ") },
+            if pending.is_empty() { "none".to_string() } else { pending.join(", ") }
         );
 
         Ok(
@@ -399,13 +1067,38 @@ impl EventsMonCli {
         client: &Client<CanisterRuntime, BotCommandContext>
     ) -> Result<SuccessResult, String> {
 
-        let icps = WalletService::balance_of(
-            user_id
-        ).await?;
+        let balances = futures::future::join_all(
+            TOKENS.iter().map(|token| WalletService::token_balance_of(user_id, *token))
+        ).await;
+
+        let notices = WalletService::take_notices(user_id);
 
         let content = format!(
-            "Balance:  \nICP: {:.8}  \n", 
-            icps as f32 / 100000000.0
+            "{}Balance:  \n{}", 
+            if notices.is_empty() {
+                "".to_string()
+            }
+            else {
+                format!(
+                    "Notices:  \n{}  \n", 
+                    notices.iter()
+                        .map(|(timestamp, text)| format!("- {} at timestamp({})  \n", text, timestamp))
+                        .collect::<String>()
+                )
+            },
+            TOKENS.iter()
+                .zip(balances)
+                .map(|(token, balance)| match balance {
+                    Ok(balance) => format!(
+                        "{}: {:.*}{}  \n", 
+                        token, 
+                        token.decimals() as usize, 
+                        token.from_units(balance),
+                        if *token == Token::Icp || SwapService::is_swappable(*token) { "" } else { " (can't be used to pay)" }
+                    ),
+                    Err(err) => format!("{}: unavailable ({})  \n", token, err),
+                })
+                .collect::<String>()
         );
         
         Ok(
@@ -427,8 +1120,14 @@ impl EventsMonCli {
         );
 
         let content = format!(
-            "Address:  \nICP: {}  \n", 
-            icp_acc_id
+            "Address:  \nICP: {}  \n{}: {}  \n", 
+            icp_acc_id,
+            TOKENS.iter()
+                .filter(|token| **token != Token::Icp)
+                .map(|token| token.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            WalletService::token_account(user_id)
         );
         
         Ok(
@@ -443,19 +1142,63 @@ impl EventsMonCli {
 
     async fn wallet_withdraw(
         user_id: Principal,
+        token: Token,
         to: Option<String>,
-        amount: f32,
+        amount: String,
         client: &Client<CanisterRuntime, BotCommandContext>
     ) -> Result<SuccessResult, String> {
-        let (block_num, account_id) = WalletService::transfer_hex(
+        let units = token.parse_units(&amount)?;
+
+        let (block_num, account_id) = if token == Token::Icp {
+            WalletService::transfer_hex(
+                user_id,
+                to,
+                u64::try_from(units).map_err(|_| format!("Invalid amount: {}", amount))?
+            ).await?
+        }
+        else {
+            WalletService::withdraw_token(
+                user_id,
+                token,
+                to,
+                units
+            ).await?
+        };
+
+        let content = format!(
+            "Withdrawal of **{}** {} to account id **{}** completed! At block index: **{}**", 
+            amount, token, account_id, block_num
+        );
+        
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::Text(content.into()), 
+                client.context().message_id().unwrap()
+            ).with_block_level_markdown(true)
+            .build()
+            .into()
+        )
+    }
+
+    async fn wallet_swap(
+        user_id: Principal,
+        token: Token,
+        amount: String,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        if token == Token::Icp {
+            return Err("Choose a token other than ICP".to_string());
+        }
+
+        let e8s = SwapService::swap_to_icp(
             user_id,
-            to,
-            (amount * 100000000.0) as u64
+            token,
+            token.parse_units(&amount)?
         ).await?;
 
         let content = format!(
-            "Withdrawn of **{}** ICP to account id **{}** completed! At block index: **{}**", 
-            amount, account_id, block_num
+            "Swapped **{}** {} to **{:.8}** ICP!", 
+            amount, token, e8s as f64 / 100000000.0
         );
         
         Ok(
@@ -468,28 +1211,78 @@ impl EventsMonCli {
         )
     }
 
-    fn wallet_logs(
+    async fn wallet_logs(
         user_id: Principal,
         page_num: usize,
         client: &Client<CanisterRuntime, BotCommandContext>
     ) -> Result<SuccessResult, String> {
-        let user = UserStorage::load(&user_id);
+        let balance = WalletService::sync_deposits(user_id).await?;
+
+        let mut txs = UserStorage::load(&user_id).txs;
+        txs.sort_by_key(|tx| tx.timestamp());
 
-        let logs = user.txs.iter()
+        // the running balance, walking back from the current one
+        let mut balances = vec![0i128; txs.len()];
+        let mut running = balance as i128;
+        for (i, tx) in txs.iter().enumerate().rev() {
+            balances[i] = running;
+            running -= tx.balance_change();
+        }
+
+        let logs = txs.iter()
+            .zip(balances.iter())
             .skip(page_num * LOG_ITEMS_PER_PAGE)
             .take(LOG_ITEMS_PER_PAGE)
-            .cloned()
-            .map(|tx| match tx {
-                UserTransaction::IcpWithdraw { amount, to, block_num, timestamp } => 
-                    format!(
-                        "Withdraw: amount({} ICP) to account_id({}) with block_num({}) at timestamp({})", 
-                        amount as f32 / 100000000.0, to, block_num, timestamp
-                    ),
-            })
+            .map(|(tx, balance)| format!(
+                "{} balance({} ICP)",
+                match tx {
+                    UserTransaction::IcpDeposit { amount, from, block_num, timestamp } => 
+                        format!(
+                            "Deposit: amount({} ICP) from account_id({}) with block_num({}) at timestamp({})", 
+                            *amount as f32 / 100000000.0, from, block_num, timestamp
+                        ),
+                    UserTransaction::IcpWithdraw { amount, to, block_num, timestamp } => 
+                        format!(
+                            "Withdraw: amount({} ICP) to account_id({}) with block_num({}) at timestamp({})", 
+                            *amount as f32 / 100000000.0, to, block_num, timestamp
+                        ),
+                    UserTransaction::MonitorTopUp { canister_id, cycles, amount, timestamp } => 
+                        format!(
+                            "Top-up: amount({} ICP) for cycles({}) to monitor({}) at timestamp({})", 
+                            *amount as f32 / 100000000.0, cycles, canister_id, timestamp
+                        ),
+                    UserTransaction::DeploymentFee { amount, block_num, timestamp } => 
+                        format!(
+                            "Deployment fee: amount({} ICP) with block_num({}) at timestamp({})", 
+                            *amount as f32 / 100000000.0, block_num, timestamp
+                        ),
+                    UserTransaction::DeploymentRefund { amount, block_num, timestamp } => 
+                        format!(
+                            "Deployment refund: amount({} ICP) with block_num({}) at timestamp({})", 
+                            *amount as f32 / 100000000.0, block_num, timestamp
+                        ),
+                    UserTransaction::TokenWithdraw { token, amount, to, block_num, timestamp } => 
+                        format!(
+                            "Withdraw: amount({} {}) to account({}) with block_num({}) at timestamp({})", 
+                            token.from_units(*amount), token, to, block_num, timestamp
+                        ),
+                    UserTransaction::TokenSwap { token, token_amount, amount, timestamp } => 
+                        format!(
+                            "Swap: amount({} {}) to amount({} ICP) at timestamp({})", 
+                            token.from_units(*token_amount), token, *amount as f32 / 100000000.0, timestamp
+                        ),
+                    UserTransaction::MonitorRefund { canister_id, cycles, amount, block_num, timestamp } => 
+                        format!(
+                            "Monitor refund: amount({} ICP) for cycles({}) from monitor({}) with block_num({}) at timestamp({})", 
+                            *amount as f32 / 100000000.0, cycles, canister_id, block_num, timestamp
+                        ),
+                },
+                *balance as f64 / 100000000.0
+            ))
             .collect::<Vec<_>>()
             .join("  \n");
 
-        let num_pages = (user.txs.len() + LOG_ITEMS_PER_PAGE-1) / LOG_ITEMS_PER_PAGE;
+        let num_pages = (txs.len() + LOG_ITEMS_PER_PAGE-1) / LOG_ITEMS_PER_PAGE;
         let page_num = (1+page_num).min(num_pages);
 
         Ok(
@@ -513,6 +1306,361 @@ impl EventsMonCli {
         )
     }
 
+    fn show_config(
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let throttle = ThrottleService::get(chat);
+        let limit = throttle.limit();
+
+        let rate_limit = if limit.per_minute == 0 {
+            "disabled".to_string()
+        }
+        else {
+            format!(
+                "{} events per minute, up to {} at once, {} queued at most", 
+                limit.per_minute, limit.burst, limit.max_queue
+            )
+        };
+
+        let permissions = PermissionService::get(chat);
+        let required = [Action::Deploy, Action::EditJobs, Action::StartStopJobs, Action::Config].iter()
+            .map(|action| format!("  \n  - {}: {}", action, permissions.required(*action)))
+            .collect::<String>();
+        let roles = permissions.roles.iter()
+            .map(|(user_id, role)| format!("  \n  - {}: {}", user_id, role))
+            .collect::<String>();
+
+        let text = format!(
+            "**Settings**:  \n- rate limit: {}  \n- events queued: {}  \n- events suppressed: {}  \n- minimum roles:{}  \n- roles:{}", 
+            rate_limit,
+            throttle.queue.len(),
+            throttle.suppressed,
+            required,
+            if roles.is_empty() { " none".to_string() } else { roles }
+        );
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(text),
+                client.context().message_id().unwrap(),
+            )
+            .with_block_level_markdown(true)
+            .build()
+            .into()
+        )
+    }
+
+    fn set_rate_limit(
+        per_minute: u32,
+        burst: Option<u32>,
+        queue: Option<u32>,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let limit = RateLimit {
+            per_minute,
+            burst: burst.unwrap_or(per_minute).max(1),
+            max_queue: queue.unwrap_or(DEFAULT_MAX_QUEUE),
+        };
+
+        ThrottleService::set_limit(chat, limit);
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text("Rate limit updated!".to_string()),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
+    fn grant_role(
+        caller: UserId,
+        user_id: String,
+        role: Role,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let user_id = Principal::from_text(&user_id)
+            .map_err(|e| format!("Invalid user id {}: {}", user_id, e))?;
+
+        PermissionService::grant(chat, caller, user_id, role)?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!("User {} is now a {}!", user_id, role)),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
+    fn set_permission(
+        caller: UserId,
+        action: Action,
+        role: Role,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        PermissionService::require(chat, caller, action, role)?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!("The {} role is now required to {}!", role, action)),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
+    async fn stop_monitor(
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        MonitorService::stop_monitor(chat.into()).await?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text("Monitor stopped!".to_string()),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
+    async fn start_monitor(
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        MonitorService::start_monitor(chat.into()).await?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text("Monitor started!".to_string()),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
+    async fn upgrade_monitor(
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let (administrator, wasm) = state::read(|s| 
+            (
+                s.administrator().clone(),
+                s.monitor_wasm().clone()
+            )
+        );
+
+        MonitorService::upgrade_monitor(chat.into(), administrator, wasm).await?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text("Monitor upgraded!".to_string()),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
+    async fn delete_monitor(
+        user_id: UserId,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let (cycles, e8s) = MonitorService::delete_monitor(chat.into(), user_id).await?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!(
+                    "Monitor deleted! **{:.8}** TCycles withdrawn, worth **{:.8}** ICP credited to your EventMon wallet", 
+                    (cycles as f64) / 1_000_000_000_000.0,
+                    (e8s as f64) / 100000000.0
+                )),
+                client.context().message_id().unwrap(),
+            )
+            .with_block_level_markdown(true)
+            .build()
+            .into()
+        )
+    }
+
+    fn transfer_owner(
+        user_id: UserId,
+        to: String,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let to = Principal::from_text(&to)
+            .map_err(|e| format!("Invalid user id {}: {}", to, e))?;
+
+        MonitorService::transfer_owner(chat.into(), user_id, to)?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!(
+                    "Ownership offered to user {}! They must run `/eventmon monitor accept` in this chat to take it", 
+                    to
+                )),
+                client.context().message_id().unwrap(),
+            )
+            .with_block_level_markdown(true)
+            .build()
+            .into()
+        )
+    }
+
+    fn fund_from(
+        user_id: String,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let user_id = Principal::from_text(&user_id)
+            .map_err(|e| format!("Invalid user id {}: {}", user_id, e))?;
+
+        MonitorService::invite_funder(chat.into(), user_id)?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!(
+                    "User {} invited to fund the monitor! They must run `/eventmon monitor accept` in this chat to start funding it", 
+                    user_id
+                )),
+                client.context().message_id().unwrap(),
+            )
+            .with_block_level_markdown(true)
+            .build()
+            .into()
+        )
+    }
+
+    fn accept(
+        user_id: UserId,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let text = if MonitorService::accept(chat.into(), user_id)? {
+            "Accepted! You are now the monitor's owner. Its cycles will be topped-up from your wallet first when needed"
+        }
+        else {
+            "Accepted! The monitor's cycles will be topped-up from your wallet when needed"
+        };
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(text.to_string()),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
+    fn remove_funder(
+        user_id: UserId,
+        funder: String,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let funder = Principal::from_text(&funder)
+            .map_err(|e| format!("Invalid user id {}: {}", funder, e))?;
+
+        MonitorService::remove_funder(chat.into(), user_id, funder)?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(format!("User {} is no longer funding the monitor!", funder)),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
+    fn show_funding(
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let (policy, spent) = MonitorService::get_funding(chat.into())?;
+
+        let text = format!(
+            "**Funding**:  \n- automatic top-ups: {}  \n- min cycles: **{:.4}** T  \n- top-up amount: **{:.4}** T  \n- max ICP per 30 days: {}  \n- ICP spent in the last 30 days: **{:.8}**  \n- pay with: {}",
+            if policy.enabled { "enabled" } else { "disabled" },
+            (policy.min_cycles as f64) / 1_000_000_000_000.0,
+            (policy.fund_cycles as f64) / 1_000_000_000_000.0,
+            policy.max_icp
+                .map(|e8s| format!("**{:.8}**", (e8s as f64) / 100000000.0))
+                .unwrap_or("unlimited".to_string()),
+            (spent as f64) / 100000000.0,
+            policy.pay_with
+                .map(|token| format!("ICP, then {}", token))
+                .unwrap_or("ICP".to_string())
+        );
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text(text),
+                client.context().message_id().unwrap(),
+            )
+            .with_block_level_markdown(true)
+            .build()
+            .into()
+        )
+    }
+
+    fn set_funding(
+        user_id: UserId,
+        changes: FundingChanges,
+        chat: Chat,
+        client: &Client<CanisterRuntime, BotCommandContext>
+    ) -> Result<SuccessResult, String> {
+        let (policy, _) = MonitorService::get_funding(chat.into())?;
+
+        let policy = FundingPolicy {
+            enabled: changes.enabled.unwrap_or(policy.enabled),
+            min_cycles: match changes.min_cycles {
+                Some(t) => parse_decimal(&t, TCYCLES_DECIMALS)?,
+                None => policy.min_cycles,
+            },
+            fund_cycles: match changes.top_up {
+                Some(t) => parse_decimal(&t, TCYCLES_DECIMALS)?,
+                None => policy.fund_cycles,
+            },
+            max_icp: match changes.max_icp {
+                Some(icp) => match Token::Icp.parse_units(&icp)? {
+                    0 => None,
+                    e8s => Some(u64::try_from(e8s).map_err(|_| format!("Invalid amount: {}", icp))?),
+                },
+                None => policy.max_icp,
+            },
+            pay_with: match changes.pay_with {
+                Some(Token::Icp) => None,
+                Some(token) => Some(token),
+                None => policy.pay_with,
+            },
+        };
+
+        MonitorService::set_funding(chat.into(), user_id, policy)?;
+
+        Ok(
+            EphemeralMessageBuilder::new(
+                MessageContentInitial::from_text("Funding policy updated!".to_string()),
+                client.context().message_id().unwrap(),
+            )
+            .build()
+            .into()
+        )
+    }
+
     fn definition(
     ) -> BotCommandDefinition {
         BotCommandDefinition {
@@ -541,4 +1689,3 @@ impl EventsMonCli {
         }
     }
 }
-
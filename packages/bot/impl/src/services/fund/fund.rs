@@ -1,9 +1,7 @@
 use std::sync::Arc;
+use async_trait::async_trait;
 use candid::Principal;
 use canfund::{
-    api::{
-        cmc::IcCyclesMintingCanister, ledger::IcLedgerCanister
-    }, 
     manager::{
         options::{
             CyclesThreshold, FundManagerOptions, 
@@ -11,21 +9,52 @@ use canfund::{
         }, 
         RegisterOpts
     }, 
-    operations::obtain::MintCycles, 
+    operations::obtain::{ObtainCycleError, ObtainCycles}, 
     FundManager
 };
-use ic_ledger_types::{
-    Subaccount, 
-    MAINNET_CYCLES_MINTING_CANISTER_ID, 
-    MAINNET_LEDGER_CANISTER_ID
+use ic_ledger_types::Subaccount;
+use crate::{
+    storage::top_up::PendingTopUpStorage, 
+    types::funding::PendingTopUp, 
+    utils::cmc::{Cmc, TopUpError}
 };
 
+// limits how much ICP can be spent on top-ups and reports their outcome
+#[async_trait]
+pub trait FundingHooks: Send + Sync {
+    // ICP (in e8s) that can still be spent, None if unlimited
+    fn remaining(
+        &self
+    ) -> Option<u128>;
+
+    // e8s is the ICP transferred to the CMC, without the ledger fee
+    fn funded(
+        &self,
+        from: Subaccount,
+        cycles: u128,
+        e8s: u64
+    );
+
+    fn failed(
+        &self,
+        details: &str
+    );
+
+    // tops-up the first funder's ICP from other tokens. Returns true if it did
+    async fn refill(
+        &self,
+        e8s: u128
+    ) -> bool;
+}
+
 #[derive(Clone)]
 pub struct FundCanisterConfig {
     pub canister_id: Principal,
-    pub from_subaccount: Subaccount,
+    // tried in order, until one can pay for the cycles
+    pub from_subaccounts: Vec<Subaccount>,
     pub min_cycles: u128,
     pub fund_cycles: u128,
+    pub hooks: Option<Arc<dyn FundingHooks>>,
 }
 
 pub struct FundService {
@@ -70,16 +99,21 @@ impl FundService {
         );
     }
 
+    pub fn remove_canister(
+        &mut self,
+        canister_id: Principal
+    ) {
+        self.manager.unregister(canister_id);
+    }
+
     fn get_obtain_cycles_config(
-        subaccount: Subaccount
+        subaccounts: Vec<Subaccount>,
+        hooks: Option<Arc<dyn FundingHooks>>
     ) -> ObtainCyclesOptions {
         ObtainCyclesOptions {
-            obtain_cycles: Arc::new(MintCycles {
-                ledger: Arc::new(IcLedgerCanister::new(MAINNET_LEDGER_CANISTER_ID)),
-                cmc: Arc::new(IcCyclesMintingCanister::new(
-                    MAINNET_CYCLES_MINTING_CANISTER_ID,
-                )),
-                from_subaccount: subaccount,
+            obtain_cycles: Arc::new(MintCyclesFromAny {
+                hooks,
+                funders: subaccounts,
             }),
         }
     }
@@ -89,7 +123,7 @@ impl FundService {
     ) -> RegisterOpts {
         RegisterOpts::new()
             .with_obtain_cycles_options(
-                Self::get_obtain_cycles_config(config.from_subaccount)
+                Self::get_obtain_cycles_config(config.from_subaccounts, config.hooks)
             )
             .with_strategy(FundStrategy::BelowThreshold(
                 CyclesThreshold::new()
@@ -99,3 +133,157 @@ impl FundService {
     }
 }
 
+// completes a top-up whose notify failed before. Returns the cycles minted
+pub async fn retry_top_up(
+    canister_id: Principal,
+    pending: PendingTopUp,
+    hooks: Option<&Arc<dyn FundingHooks>>
+) -> Result<u128, TopUpError> {
+    match Cmc::notify_top_up(canister_id, pending.block_index).await {
+        Ok(cycles) => {
+            // only once, if it was retried concurrently
+            if PendingTopUpStorage::remove(&canister_id).is_some() {
+                if let Some(hooks) = hooks {
+                    hooks.funded(pending.from, cycles, pending.e8s);
+                }
+            }
+            Ok(cycles)
+        },
+        Err(err) => {
+            ic_cdk::println!("error: funding canister {}: {}", canister_id, err);
+            if matches!(err, TopUpError::Rejected { .. }) {
+                PendingTopUpStorage::remove(&canister_id);
+            }
+            Err(err)
+        }
+    }
+}
+
+// mints the cycles from the first funder able to pay for them
+struct MintCyclesFromAny {
+    hooks: Option<Arc<dyn FundingHooks>>,
+    funders: Vec<Subaccount>,
+}
+
+impl MintCyclesFromAny {
+    // a failed notify is stored to be retried, as the funder's ICP is already at the CMC
+    async fn top_up(
+        &self,
+        from: Subaccount,
+        canister_id: Principal,
+        e8s: u64
+    ) -> Result<u128, TopUpError> {
+        match Cmc::top_up(from, canister_id, e8s).await {
+            Ok(cycles) => {
+                if let Some(hooks) = &self.hooks {
+                    hooks.funded(from, cycles, e8s);
+                }
+                Ok(cycles)
+            },
+            Err(err) => {
+                ic_cdk::println!("error: funding canister {}: {}", canister_id, err);
+                if let TopUpError::Notify { block_index, .. } = err {
+                    PendingTopUpStorage::save(canister_id, PendingTopUp {
+                        from,
+                        block_index,
+                        e8s,
+                    });
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ObtainCycles for MintCyclesFromAny {
+    async fn obtain_cycles(
+        &self,
+        amount: u128,
+        target_canister_id: Principal
+    ) -> Result<u128, ObtainCycleError> {
+        // the ICP of a top-up still pending is already spent: complete it instead of paying for another
+        if let Some(pending) = PendingTopUpStorage::load(&target_canister_id) {
+            return retry_top_up(target_canister_id, pending, self.hooks.as_ref()).await
+                .map_err(|err| ObtainCycleError {
+                    details: err.to_string(),
+                    can_retry: true,
+                });
+        }
+
+        let cost = Cmc::cycles_to_icp(amount).await
+            .map_err(|details| ObtainCycleError {
+                details,
+                can_retry: true,
+            })?;
+
+        if let Some(hooks) = &self.hooks {
+            if let Some(remaining) = hooks.remaining() {
+                if cost > remaining {
+                    let details = format!(
+                        "Funding budget exceeded: {:.8} ICP needed, {:.8} ICP left", 
+                        cost as f64 / 100000000.0, 
+                        remaining as f64 / 100000000.0
+                    );
+                    hooks.failed(&details);
+                    return Err(ObtainCycleError {
+                        details,
+                        can_retry: false,
+                    });
+                }
+            }
+        }
+
+        let e8s = cost as u64;
+        let mut last_err = None;
+
+        // only a failed transfer moves on to the next funder
+        for from in &self.funders {
+            match self.top_up(*from, target_canister_id, e8s).await {
+                Ok(cycles) => {
+                    return Ok(cycles);
+                },
+                Err(TopUpError::Transfer(err)) => {
+                    last_err = Some(err);
+                },
+                Err(err) => {
+                    return Err(ObtainCycleError {
+                        details: err.to_string(),
+                        can_retry: true,
+                    });
+                }
+            }
+        }
+
+        // all funders ran out of ICP: pay with another token, if the hooks can
+        if let (Some(hooks), Some(from)) = (&self.hooks, self.funders.first()) {
+            if hooks.refill(cost).await {
+                match self.top_up(*from, target_canister_id, e8s).await {
+                    Ok(cycles) => {
+                        return Ok(cycles);
+                    },
+                    Err(TopUpError::Transfer(err)) => {
+                        last_err = Some(err);
+                    },
+                    Err(err) => {
+                        return Err(ObtainCycleError {
+                            details: err.to_string(),
+                            can_retry: true,
+                        });
+                    }
+                }
+            }
+        }
+
+        let details = last_err.unwrap_or_else(|| "No funders".to_string());
+
+        if let Some(hooks) = &self.hooks {
+            hooks.failed(&details);
+        }
+
+        Err(ObtainCycleError {
+            details,
+            can_retry: true,
+        })
+    }
+}
@@ -0,0 +1,261 @@
+use std::{sync::Arc, time::Duration};
+use async_trait::async_trait;
+use candid::Principal;
+use ic_ledger_types::{Subaccount, DEFAULT_FEE};
+use oc_bots_sdk::types::Chat;
+use crate::{
+    services::{
+        fund::{retry_top_up, FundingHooks},
+        monitor::MONITOR_CYCLES_CHECK_INTERVAL,
+        swap::SwapService,
+        throttle::ThrottleService,
+        wallet::wallet::WalletService
+    },
+    storage::{monitor::MonitorStorage, top_up::PendingTopUpStorage, user::UserStorage},
+    types::{
+        monitor::{Monitor, MonitorId},
+        user::UserTransaction
+    },
+    utils::{chat::has_text_api_key, cmc::Cmc, ic::get_canister_status, nat::nat_to_u128}
+};
+
+const DAY: u64 = 24 * 60 * 60 * 1_000_000_000; // in nanoseconds
+const ALERT_INTERVAL: u64 = DAY; // at most one alert per day and monitor
+const LOW_RUNWAY_DAYS: u128 = 2 * 7; // 2 weeks
+const TOP_UP_RETRY_INTERVAL: u64 = 10 * 60; // every 10 minutes
+
+pub struct FundingService;
+
+impl FundingService {
+    pub fn start(
+    ) {
+        ic_cdk_timers::set_timer_interval(
+            Duration::from_secs(MONITOR_CYCLES_CHECK_INTERVAL),
+            || ic_cdk::spawn(Self::check_runways())
+        );
+
+        // complete the top-ups whose ICP is at the CMC, but whose notify failed
+        ic_cdk_timers::set_timer_interval(
+            Duration::from_secs(TOP_UP_RETRY_INTERVAL),
+            || ic_cdk::spawn(Self::retry_top_ups())
+        );
+    }
+
+    pub fn hooks(
+        canister_id: Principal
+    ) -> Arc<dyn FundingHooks> {
+        Arc::new(MonitorFundingHooks {
+            canister_id,
+        })
+    }
+
+    async fn retry_top_ups(
+    ) {
+        for canister_id in PendingTopUpStorage::canister_ids() {
+            let Some(pending) = PendingTopUpStorage::load(&canister_id) else {
+                continue;
+            };
+
+            // the hooks do nothing for canisters other than the monitors, ie: the bot itself
+            let hooks = Self::hooks(canister_id);
+            let _ = retry_top_up(canister_id, pending, Some(&hooks)).await;
+        }
+    }
+
+    async fn check_runways(
+    ) {
+        MonitorStorage::for_each_async(async |id, mon| {
+            if !mon.funding().enabled {
+                return;
+            }
+
+            if let Err(err) = Self::check_runway(id, mon).await {
+                ic_cdk::println!("error: checking the runway of monitor({}): {}", id, err);
+            }
+        }).await;
+    }
+
+    async fn check_runway(
+        mon_id: MonitorId,
+        mon: Monitor
+    ) -> Result<(), String> {
+        let status = get_canister_status(mon.canister_id).await?;
+        let cycles = nat_to_u128(status.cycles);
+        let now = ic_cdk::api::time();
+
+        // the balance drop since the last check includes the cost of the jobs,
+        // unless the canister was topped-up meanwhile
+        let observed_per_day = match mon.cycles_sample {
+            Some((at, last_cycles)) if last_cycles > cycles && now > at => {
+                (last_cycles - cycles) * DAY as u128 / (now - at) as u128
+            },
+            _ => {
+                0
+            }
+        };
+        let burned_per_day = nat_to_u128(status.idle_cycles_burned_per_day)
+            .max(observed_per_day)
+            .max(1);
+
+        let mut e8s = 0u128;
+        for user_id in mon.funders() {
+            e8s += WalletService::balance_of(user_id).await? as u128;
+        }
+        let wallet_cycles = Cmc::icp_to_cycles(e8s).await?;
+
+        let runway_days = (wallet_cycles + cycles.saturating_sub(mon.funding().min_cycles)) / burned_per_day;
+
+        // reload it, as it could have changed while waiting
+        let Some(mut mon) = MonitorStorage::load(&mon_id) else {
+            return Ok(());
+        };
+
+        mon.cycles_sample = Some((now, cycles));
+
+        if runway_days < LOW_RUNWAY_DAYS {
+            Self::alert(
+                &mut mon,
+                format!(
+                    "the EventMon wallets funding it can only keep it running for about **{}** more days. Please deposit ICP at the address shown by `/eventmon wallet address`",
+                    runway_days
+                )
+            );
+        }
+
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(())
+    }
+
+    // sends the alert to the owner and the co-funders only, as a notice shown by `/eventmon wallet balance`
+    // and, if they registered an API key for their direct chat with the bot, as a direct message
+    fn alert(
+        mon: &mut Monitor,
+        text: String
+    ) {
+        let now = ic_cdk::api::time();
+        if mon.funding_alerted_at.is_some_and(|at| at + ALERT_INTERVAL > now) {
+            return;
+        }
+        mon.funding_alerted_at = Some(now);
+
+        let text = format!(
+            "⚠️ the monitor canister {}: {}",
+            mon.canister_id, text
+        );
+
+        ic_cdk::spawn(ThrottleService::post(
+            mon.chat, 
+            vec![format!("@UserId({}) {}", mon.owner, text)]
+        ));
+
+        for user_id in mon.funders() {
+            WalletService::add_notice(user_id, text.clone());
+
+            // funders may not be members of the monitor's chat
+            let chat = Chat::Direct(user_id);
+            if chat != mon.chat && has_text_api_key(chat) {
+                ic_cdk::spawn(ThrottleService::post(chat, vec![text.clone()]));
+            }
+        }
+    }
+}
+
+// tracks the ICP spent on top-ups on the monitor, capped by its funding policy,
+// records them in the funder's transactions and alerts the owner when they fail
+struct MonitorFundingHooks {
+    canister_id: Principal,
+}
+
+#[async_trait]
+impl FundingHooks for MonitorFundingHooks {
+    fn remaining(
+        &self
+    ) -> Option<u128> {
+        let mon = MonitorStorage::load_by_canister_id(&self.canister_id)?;
+        let max_icp = mon.funding().max_icp?;
+
+        Some(max_icp.saturating_sub(mon.funding_spent(ic_cdk::api::time())) as u128)
+    }
+
+    fn funded(
+        &self,
+        from: Subaccount,
+        cycles: u128,
+        e8s: u64
+    ) {
+        let Some(mut mon) = MonitorStorage::load_by_canister_id(&self.canister_id) else {
+            return;
+        };
+
+        let now = ic_cdk::api::time();
+        mon.add_funding_spent(e8s, now);
+
+        if let Some(user_id) = mon.funders().into_iter().find(|user_id| Subaccount::from(*user_id) == from) {
+            let mut user = UserStorage::load(&user_id);
+            user.txs.push(UserTransaction::MonitorTopUp {
+                canister_id: self.canister_id,
+                cycles,
+                amount: e8s,
+                timestamp: (now / 1_000_000_000) as _,
+            });
+            UserStorage::save(user_id, user);
+        }
+
+        MonitorStorage::save(mon.chat.into(), mon);
+    }
+
+    fn failed(
+        &self,
+        details: &str
+    ) {
+        let Some(mut mon) = MonitorStorage::load_by_canister_id(&self.canister_id) else {
+            return;
+        };
+
+        FundingService::alert(
+            &mut mon,
+            format!(
+                "topping it up failed: {}. Please deposit ICP at the address shown by `/eventmon wallet address`",
+                details
+            )
+        );
+
+        MonitorStorage::save(mon.chat.into(), mon);
+    }
+    async fn refill(
+        &self,
+        e8s: u128
+    ) -> bool {
+        let Some(mon) = MonitorStorage::load_by_canister_id(&self.canister_id) else {
+            return false;
+        };
+
+        let Some(token) = mon.funding().pay_with else {
+            return false;
+        };
+
+        // the ICP minted to cycles, plus the fee of the transfer to the CMC
+        // (the swap's own ICP fees are included by the estimate)
+        let e8s = e8s as u64 + DEFAULT_FEE.e8s();
+
+        let res = match SwapService::estimate_input(token, e8s).await {
+            Ok(amount) => {
+                SwapService::swap_to_icp(mon.owner, token, amount).await
+            },
+            Err(err) => {
+                Err(err)
+            }
+        };
+
+        match res {
+            Ok(_) => {
+                true
+            },
+            Err(err) => {
+                ic_cdk::println!("error: paying the top-up of monitor({}) with {}: {}", self.canister_id.to_text(), token, err);
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod funding;
+
+pub use funding::*;
\ No newline at end of file
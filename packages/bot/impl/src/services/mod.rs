@@ -1,3 +1,7 @@
 pub mod monitor;
 pub mod wallet;
-pub mod fund;
\ No newline at end of file
+pub mod fund;
+pub mod throttle;
+pub mod permission;
+pub mod funding;
+pub mod swap;
\ No newline at end of file
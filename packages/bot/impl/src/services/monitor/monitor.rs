@@ -1,38 +1,58 @@
 use std::cell::RefCell;
 use candid::{Encode, Principal};
 use ic_cdk::api::management_canister::main::{
-    create_canister, install_code, start_canister, stop_canister, 
+    create_canister, delete_canister, install_code, start_canister, stop_canister, 
     CanisterIdRecord, CanisterInstallMode, CanisterSettings, 
     CanisterStatusType, CreateCanisterArgument, InstallCodeArgument, LogVisibility
 };
 use monitor_api::{
     lifecycle::init::InitOrUpgradeArgs, 
-    queries::list_jobs::{Job, ListJobsArgs, ListJobsResult}, 
+    queries::{
+        get_job::{GetJobArgs, GetJobResult, JobDetails}, 
+        get_job_runs::{GetJobRunsArgs, GetJobRunsResult, JobRuns}, 
+        list_jobs::{Job, ListJobsArgs, ListJobsResult}, 
+        list_outbox::{ListOutboxArgs, ListOutboxResult, OutboxEntry}
+    }, 
     updates::{
         add_job::{AddJobArgs, AddJobResult, JobId}, 
         del_job::{DelJobArgs, DelJobResult}, 
+        drop_outbox::{DropOutboxArgs, DropOutboxResult}, 
+        flush_outbox::FlushOutboxResult, 
+        seek_job::{SeekJobArgs, SeekJobResult, SeekPosition}, 
         start_job::{StartJobArgs, StartJobResult}, 
-        stop_job::{StopJobArgs, StopJobResult}
+        stop_job::{StopJobArgs, StopJobResult}, 
+        update_job::{UpdateJobArgs, UpdateJobResult}, 
+        withdraw_cycles::WithdrawCyclesResult
     }
 };
 use oc_bots_sdk::types::Chat;
 use crate::{
     consts::DEPLOY_CANISTER_CYCLES, 
-    services::fund::{FundCanisterConfig, FundService}, 
+    services::{
+        fund::{FundCanisterConfig, FundService}, 
+        funding::FundingService, 
+        wallet::wallet::WalletService
+    }, 
     state::MonitorWasm, 
     storage::monitor::MonitorStorage, 
-    types::monitor::{
-        Monitor, MonitorId, MonitorState, MonitorStatus
+    types::{
+        funding::FundingPolicy, 
+        monitor::{Monitor, MonitorId, MonitorState, MonitorStatus}, 
+        user::{CreditReason, PendingCredit}
     }, 
     utils::{
+        cmc::Cmc, 
         ic::get_canister_status, 
         nat::nat_to_u128
     }
 };
 
 pub const MIN_MONITOR_CYCLES: u128 = 500_000_000_000;
-const FUND_MONITOR_CYCLES: u128 =    100_000_000_000;
-const MONITOR_CYCLES_CHECK_INTERVAL: u64 = 6 * 60 * 60; // every 6 hours
+pub const FUND_MONITOR_CYCLES: u128 =    100_000_000_000;
+const MIN_TOP_UP_CYCLES: u128 =         10_000_000_000;
+// below this, the canister could be frozen before it is topped-up
+const MIN_FUNDING_THRESHOLD_CYCLES: u128 = 100_000_000_000;
+pub const MONITOR_CYCLES_CHECK_INTERVAL: u64 = 6 * 60 * 60; // every 6 hours
 
 const MIN_INTERVAL: u32 = 60; // 60 seconds
 const MAX_INTERVAL: u32 = 24 * 60 * 60; // 24 hours
@@ -51,15 +71,10 @@ impl MonitorService {
         let mut canisters = vec![];
 
         MonitorStorage::for_each_mut(&mut |_mon_id, mon| {
-            // for each monitor, use its owner's subaccount to top-up the canister
-            canisters.push(
-                FundCanisterConfig {
-                    canister_id: mon.canister_id.clone(),
-                    from_subaccount: mon.owner.into(),
-                    min_cycles: MIN_MONITOR_CYCLES,
-                    fund_cycles: FUND_MONITOR_CYCLES,
-                }
-            );
+            // for each monitor, use its owner's and co-funders' subaccounts to top-up the canister
+            if mon.funding().enabled {
+                canisters.push(Self::fund_config(&mon));
+            }
         });
 
         FUND_SERVICE.with_borrow_mut(|service| {
@@ -67,7 +82,10 @@ impl MonitorService {
                 canisters,
                 MONITOR_CYCLES_CHECK_INTERVAL
             );
-        })
+        });
+
+        // warn the owners before their wallets run out, and complete the top-ups whose notify failed
+        FundingService::start();
     }
 
     pub async fn deploy(
@@ -125,31 +143,401 @@ impl MonitorService {
         }).await
             .map_err(|e| e.1)?;
 
+        let mon = Monitor::new(chat, user_id, canister_id, wasm.hash);
+
         // 4th: auto top-up de canister from users's wallet
+        Self::register_funding(&mon);
+
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(canister_id)
+    }
+
+    pub fn transfer_owner(
+        mon_id: MonitorId,
+        caller: Principal,
+        user_id: Principal
+    ) -> Result<(), String> {
+        let mut mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        if mon.owner != caller {
+            return Err("Only the monitor's owner can transfer it".to_string());
+        }
+
+        if mon.owner == user_id {
+            return Err("User is already the monitor's owner".to_string());
+        }
+
+        mon.pending_owner = Some(user_id);
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(())
+    }
+
+    pub fn invite_funder(
+        mon_id: MonitorId,
+        user_id: Principal
+    ) -> Result<(), String> {
+        let mut mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        if mon.funders().contains(&user_id) {
+            return Err("User is already funding the monitor".to_string());
+        }
+
+        let pending = mon.pending_funders.get_or_insert_with(Vec::new);
+        if !pending.contains(&user_id) {
+            pending.push(user_id);
+        }
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(())
+    }
+
+    // accepts the ownership and/or the funding of the monitor offered to the caller
+    // returns if the caller accepted the ownership of the monitor, rather than only funding it
+    pub fn accept(
+        mon_id: MonitorId,
+        caller: Principal
+    ) -> Result<bool, String> {
+        let mut mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        let mut accepted = false;
+        let mut owner = false;
+
+        if mon.pending_owner == Some(caller) {
+            mon.pending_owner = None;
+            mon.owner = caller;
+            accepted = true;
+            owner = true;
+        }
+
+        if let Some(pending) = &mut mon.pending_funders {
+            if let Some(index) = pending.iter().position(|user| *user == caller) {
+                pending.remove(index);
+                let funders = mon.funders.get_or_insert_with(Vec::new);
+                if !funders.contains(&caller) {
+                    funders.push(caller);
+                }
+                accepted = true;
+            }
+        }
+
+        if !accepted {
+            return Err("Nothing to accept".to_string());
+        }
+
+        Self::register_funding(&mon);
+
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(owner)
+    }
+
+    pub fn remove_funder(
+        mon_id: MonitorId,
+        caller: Principal,
+        user_id: Principal
+    ) -> Result<(), String> {
+        let mut mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        if caller != mon.owner && caller != user_id {
+            return Err("Only the monitor's owner or the funder can remove a funder".to_string());
+        }
+
+        let mut removed = false;
+        if let Some(funders) = &mut mon.funders {
+            let len = funders.len();
+            funders.retain(|user| *user != user_id);
+            removed |= funders.len() != len;
+        }
+        if let Some(pending) = &mut mon.pending_funders {
+            let len = pending.len();
+            pending.retain(|user| *user != user_id);
+            removed |= pending.len() != len;
+        }
+
+        if !removed {
+            return Err("User is not funding the monitor".to_string());
+        }
+
+        Self::register_funding(&mon);
+
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(())
+    }
+
+    pub async fn stop_monitor(
+        mon_id: MonitorId
+    ) -> Result<(), String> {
+        let mut mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        if let MonitorState::Stopped = mon.state {
+            return Err("Monitor already stopped".to_string());
+        }
+
+        stop_canister(CanisterIdRecord {
+            canister_id: mon.canister_id
+        }).await.map_err(|e| e.1)?;
+
+        mon.state = MonitorState::Stopped;
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(())
+    }
+
+    pub async fn start_monitor(
+        mon_id: MonitorId
+    ) -> Result<(), String> {
+        let mut mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        if let MonitorState::Running = mon.state {
+            return Err("Monitor already running".to_string());
+        }
+
+        start_canister(CanisterIdRecord {
+            canister_id: mon.canister_id
+        }).await.map_err(|e| e.1)?;
+
+        mon.state = MonitorState::Running;
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(())
+    }
+
+    pub async fn upgrade_monitor(
+        mon_id: MonitorId,
+        administrator: Principal,
+        wasm: MonitorWasm
+    ) -> Result<(), String> {
+        let mut mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        if wasm.hash == mon.wasm_hash {
+            return Err("Monitor already up to date".to_string());
+        }
+
+        let running = matches!(mon.state, MonitorState::Running);
+        if running {
+            stop_canister(CanisterIdRecord {
+                canister_id: mon.canister_id
+            }).await.map_err(|e| e.1)?;
+        }
+
+        let res = install_code(
+            InstallCodeArgument { 
+                mode: CanisterInstallMode::Upgrade(None), 
+                canister_id: mon.canister_id, 
+                wasm_module: wasm.image, 
+                arg: Encode!(&InitOrUpgradeArgs { 
+                    administrator, 
+                    bot_canister_id: ic_cdk::api::id(),
+                }).unwrap()
+            }
+        ).await.map_err(|e| e.1);
+
+        // restart it even if the upgrade failed
+        if running {
+            if let Err(err) = start_canister(CanisterIdRecord {
+                canister_id: mon.canister_id
+            }).await {
+                ic_cdk::println!("error: starting monitor({}): {}", mon.canister_id.to_text(), err.1);
+            }
+        }
+
+        res?;
+
+        mon.wasm_hash = wasm.hash;
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(())
+    }
+
+    // withdraws the remaining cycles back to the bot, crediting their worth in ICP to the owner's wallet,
+    // and deletes the canister. Each step can be retried if a later one fails. Returns (cycles, e8s) withdrawn
+    pub async fn delete_monitor(
+        mon_id: MonitorId,
+        caller: Principal
+    ) -> Result<(u128, u64), String> {
+        let mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        if mon.owner != caller {
+            return Err("Only the monitor's owner can delete it".to_string());
+        }
+
+        let canister_id = mon.canister_id;
+
+        // priced before withdrawing, so the credit is stored right after it
+        let e8s_per_tcycles = Cmc::cycles_to_icp(1_000_000_000_000).await?;
+
+        // unregistered first, so no top-up lands while, or after, its cycles are withdrawn
         FUND_SERVICE.with_borrow_mut(|service| {
-            service.add_canister(FundCanisterConfig {
-                canister_id,
-                from_subaccount: user_id.into(),
-                min_cycles: MIN_MONITOR_CYCLES,
-                fund_cycles: FUND_MONITOR_CYCLES,
-            });
+            service.remove_canister(canister_id);
         });
 
-        MonitorStorage::save(
-            mon_id, 
-            Monitor::new(chat, user_id, canister_id, wasm.hash)
-        );
+        // the canister must be running to send its cycles back, also if a previous attempt stopped it
+        if let Err(err) = start_canister(CanisterIdRecord { canister_id }).await {
+            Self::register_funding(&mon);
+            return Err(err.1);
+        }
 
-        Ok(canister_id)
+        let cycles = match ic_cdk::call::<(), (WithdrawCyclesResult, )>(
+            canister_id, 
+            "withdraw_cycles", 
+            ()
+        ).await.map_err(|e| e.1).and_then(|r| r.0) {
+            Ok(cycles) => {
+                cycles
+            },
+            Err(err) => {
+                // don't lose the cycles: keep the monitor, and its funding, so deleting can be retried
+                ic_cdk::println!("error: withdrawing cycles from monitor({}): {}", canister_id.to_text(), err);
+                Self::register_funding(&mon);
+                return Err(format!("Could not withdraw the monitor's cycles: {}", err));
+            }
+        };
+
+        let e8s = (cycles * e8s_per_tcycles / 1_000_000_000_000) as u64;
+        if e8s > 0 {
+            WalletService::add_credit(mon.owner, PendingCredit { 
+                from: None, 
+                amount: e8s, 
+                reason: CreditReason::MonitorDeleted { 
+                    canister_id, 
+                    cycles 
+                },
+            });
+        }
+
+        stop_canister(CanisterIdRecord { canister_id })
+            .await.map_err(|e| e.1)?;
+
+        delete_canister(CanisterIdRecord { canister_id })
+            .await.map_err(|e| e.1)?;
+
+        MonitorStorage::remove(&mon_id);
+
+        WalletService::settle_credits(mon.owner).await;
+
+        Ok((cycles, e8s))
+    }
+
+    pub fn get_funding(
+        mon_id: MonitorId
+    ) -> Result<(FundingPolicy, u64), String> {
+        let mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        Ok((mon.funding(), mon.funding_spent(ic_cdk::api::time())))
+    }
+
+    pub fn set_funding(
+        mon_id: MonitorId,
+        caller: Principal,
+        policy: FundingPolicy
+    ) -> Result<(), String> {
+        let mut mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        if mon.owner != caller {
+            return Err("Only the monitor's owner can change its funding".to_string());
+        }
+
+        if policy.min_cycles < MIN_FUNDING_THRESHOLD_CYCLES {
+            return Err(format!("Min cycles too low. Min: {} cycles", MIN_FUNDING_THRESHOLD_CYCLES));
+        }
+
+        if policy.fund_cycles < MIN_TOP_UP_CYCLES {
+            return Err(format!("Top-up amount too low. Min: {} cycles", MIN_TOP_UP_CYCLES));
+        }
+
+        mon.funding = Some(policy);
+        Self::register_funding(&mon);
+        MonitorStorage::save(mon_id, mon);
+
+        Ok(())
     }
 
-    pub async fn add_canister_job(
+    // (re)registers the canister with the fund service, following its funding policy
+    fn register_funding(
+        mon: &Monitor
+    ) {
+        FUND_SERVICE.with_borrow_mut(|service| {
+            if mon.funding().enabled {
+                service.add_canister(Self::fund_config(mon));
+            }
+            else {
+                service.remove_canister(mon.canister_id);
+            }
+        });
+    }
+
+    fn fund_config(
+        mon: &Monitor
+    ) -> FundCanisterConfig {
+        let policy = mon.funding();
+
+        FundCanisterConfig {
+            canister_id: mon.canister_id,
+            from_subaccounts: mon.funders().into_iter()
+                .map(|user_id| user_id.into())
+                .collect(),
+            min_cycles: policy.min_cycles,
+            fund_cycles: policy.fund_cycles,
+            hooks: Some(FundingService::hooks(mon.canister_id)),
+        }
+    }
+
+    pub async fn add_job(
         mon_id: MonitorId,
-        canister_id: Principal,
-        method_name: String,
-        interval: u32,
-        batch_size: u32,
-        output_template: String,
+        args: AddJobArgs
     ) -> Result<JobId, String> {
         let mut mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
             mon
@@ -158,24 +546,17 @@ impl MonitorService {
             return Err(format!("Unknown monitor id: {}", mon_id));
         };
 
-        if interval < MIN_INTERVAL {
+        if args.interval < MIN_INTERVAL {
             return Err(format!("Interval too low. Min: {}", MIN_INTERVAL));
         }
-        else if interval > MAX_INTERVAL {
+        else if args.interval > MAX_INTERVAL {
             return Err(format!("Interval too high. Max: {}", MAX_INTERVAL));
         }
         
         let job_id = ic_cdk::call::<(AddJobArgs, ), (AddJobResult, )>(
             mon.canister_id, 
             "add_job", 
-            (AddJobArgs {
-                canister_id,
-                method_name,
-                interval,
-                batch_size,
-                output_template,
-                offset: 0,
-            }, )
+            (args, )
         ).await.map_err(|e| e.1)?.0?;
 
         mon.jobs.push(job_id);
@@ -228,6 +609,35 @@ impl MonitorService {
         Ok(())
     }
 
+    pub async fn update_job(
+        mon_id: MonitorId,
+        args: UpdateJobArgs
+    ) -> Result<(), String> {
+        let mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        if let Some(interval) = args.interval {
+            if interval < MIN_INTERVAL {
+                return Err(format!("Interval too low. Min: {}", MIN_INTERVAL));
+            }
+            else if interval > MAX_INTERVAL {
+                return Err(format!("Interval too high. Max: {}", MAX_INTERVAL));
+            }
+        }
+
+        ic_cdk::call::<(UpdateJobArgs, ), (UpdateJobResult, )>(
+            mon.canister_id, 
+            "update_job", 
+            (args, )
+        ).await.map_err(|e| e.1)?.0?;
+
+        Ok(())
+    }
+
     pub async fn del_job(
         mon_id: MonitorId,
         job_id: JobId
@@ -247,12 +657,87 @@ impl MonitorService {
             },)
         ).await.map_err(|e| e.1)?.0?;
 
-        mon.jobs.retain(|id| *id == job_id);
+        mon.jobs.retain(|id| *id != job_id);
+        if let Some(delivered) = &mut mon.delivered {
+            delivered.retain(|(id, _)| *id != job_id);
+        }
         MonitorStorage::save(mon_id, mon);
 
         Ok(())
     }
 
+    pub async fn get_job(
+        mon_id: MonitorId,
+        job_id: JobId
+    ) -> Result<JobDetails, String> {
+        let mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        let job = ic_cdk::call::<(GetJobArgs, ), (GetJobResult, )>(
+            mon.canister_id, 
+            "get_job", 
+            (GetJobArgs {
+                job_id,
+            },)
+        ).await.map_err(|e| e.1)?.0?;
+
+        Ok(job)
+    }
+
+    pub async fn get_job_runs(
+        mon_id: MonitorId,
+        job_id: JobId,
+        offset: u32,
+        size: u32
+    ) -> Result<JobRuns, String> {
+        let mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        let runs = ic_cdk::call::<(GetJobRunsArgs, ), (GetJobRunsResult, )>(
+            mon.canister_id, 
+            "get_job_runs", 
+            (GetJobRunsArgs {
+                job_id,
+                offset,
+                size,
+            },)
+        ).await.map_err(|e| e.1)?.0?;
+
+        Ok(runs)
+    }
+
+    pub async fn seek_job(
+        mon_id: MonitorId,
+        job_id: JobId,
+        position: SeekPosition
+    ) -> Result<u64, String> {
+        let mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        let offset = ic_cdk::call::<(SeekJobArgs, ), (SeekJobResult, )>(
+            mon.canister_id, 
+            "seek_job", 
+            (SeekJobArgs {
+                job_id,
+                position,
+            },)
+        ).await.map_err(|e| e.1)?.0?;
+
+        Ok(offset)
+    }
+
     pub async fn list_jobs(
         mon_id: MonitorId,
         page: u32
@@ -276,6 +761,74 @@ impl MonitorService {
         Ok(jobs)
     }
 
+    pub async fn list_outbox(
+        mon_id: MonitorId,
+        page: u32,
+        dead: bool
+    ) -> Result<Vec<OutboxEntry>, String> {
+        let mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        let items = ic_cdk::call::<(ListOutboxArgs, ), (ListOutboxResult, )>(
+            mon.canister_id, 
+            "list_outbox", 
+            (ListOutboxArgs {
+                offset: page * ITEMS_PER_PAGE,
+                size: ITEMS_PER_PAGE,
+                dead
+            },)
+        ).await.map_err(|e| e.1)?.0?;
+
+        Ok(items)
+    }
+
+    pub async fn flush_outbox(
+        mon_id: MonitorId
+    ) -> Result<u32, String> {
+        let mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        let count = ic_cdk::call::<(), (FlushOutboxResult, )>(
+            mon.canister_id, 
+            "flush_outbox", 
+            ()
+        ).await.map_err(|e| e.1)?.0?;
+
+        Ok(count)
+    }
+
+    pub async fn drop_outbox(
+        mon_id: MonitorId,
+        id: Option<u64>,
+        dead: bool
+    ) -> Result<u32, String> {
+        let mon = if let Some(mon) = MonitorStorage::load(&mon_id) {
+            mon
+        }
+        else {
+            return Err("Unknown monitor id".to_string());
+        };
+
+        let count = ic_cdk::call::<(DropOutboxArgs, ), (DropOutboxResult, )>(
+            mon.canister_id, 
+            "drop_outbox", 
+            (DropOutboxArgs {
+                id,
+                dead
+            },)
+        ).await.map_err(|e| e.1)?.0?;
+
+        Ok(count)
+    }
+
     pub async fn get_status(
         mon_id: MonitorId
     ) -> Result<MonitorStatus, String> {
@@ -291,12 +844,19 @@ impl MonitorService {
             .map(|s| MonitorStatus {
                 status: match s.status {
                     CanisterStatusType::Running => MonitorState::Running,
-                    _ => MonitorState::Idle,
+                    _ => match mon.state {
+                        MonitorState::Stopped => MonitorState::Stopped,
+                        _ => MonitorState::Idle,
+                    },
                 },
                 module_hash: hex::encode(s.module_hash.unwrap()),
                 memory_size: nat_to_u128(s.memory_size),
                 cycles: nat_to_u128(s.cycles),
                 idle_cycles_burned_per_day: nat_to_u128(s.idle_cycles_burned_per_day),
+                owner: mon.owner,
+                funders: mon.funders().into_iter().skip(1).collect(),
+                pending_owner: mon.pending_owner,
+                pending_funders: mon.pending_funders.clone().unwrap_or_default(),
             })?;
 
         Ok(status)
@@ -371,4 +931,4 @@ impl MonitorService {
             }
         }).await;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,3 @@
+pub mod permission;
+
+pub use permission::*;
\ No newline at end of file
@@ -0,0 +1,65 @@
+use oc_bots_sdk::types::Chat;
+use crate::{
+    storage::{monitor::MonitorStorage, permission::PermissionStorage}, 
+    types::{
+        monitor::MonitorId, 
+        permission::{Action, ChatPermissions, Role}, 
+        user::UserId
+    }
+};
+
+pub struct PermissionService;
+
+impl PermissionService {
+    pub fn authorize(
+        user_id: UserId,
+        chat: Chat,
+        action: Action
+    ) -> Result<(), String> {
+        let id = chat.into();
+
+        PermissionStorage::load(&id)
+            .authorize(Self::owner(&id), user_id, action)
+    }
+
+    pub fn get(
+        chat: Chat
+    ) -> ChatPermissions {
+        PermissionStorage::load(&chat.into())
+    }
+
+    pub fn grant(
+        chat: Chat,
+        caller: UserId,
+        user_id: UserId,
+        role: Role
+    ) -> Result<(), String> {
+        let id = chat.into();
+        let mut permissions = PermissionStorage::load(&id);
+        permissions.grant(Self::owner(&id), caller, user_id, role)?;
+        PermissionStorage::save(id, permissions);
+
+        Ok(())
+    }
+
+    pub fn require(
+        chat: Chat,
+        caller: UserId,
+        action: Action,
+        role: Role
+    ) -> Result<(), String> {
+        let id = chat.into();
+        let mut permissions = PermissionStorage::load(&id);
+        permissions.require(Self::owner(&id), caller, action, role)?;
+        PermissionStorage::save(id, permissions);
+
+        Ok(())
+    }
+
+    fn owner(
+        id: &MonitorId
+    ) -> Option<UserId> {
+        MonitorStorage::load(id)
+            .map(|mon| mon.owner)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod swap;
+
+pub use swap::*;
\ No newline at end of file
@@ -0,0 +1,238 @@
+use std::{cell::RefCell, collections::BTreeSet};
+use candid::Principal;
+use ic_ledger_types::{DEFAULT_FEE, MAINNET_LEDGER_CANISTER_ID};
+use crate::{
+    services::wallet::wallet::WalletService, 
+    storage::swap_route::SwapRouteStorage, 
+    types::{
+        token::{SwapRoute, Token}, 
+        user::{CreditReason, PendingCredit}
+    }, 
+    utils::{icpswap::IcpSwapPool, icrc::Icrc}
+};
+
+const MAX_SLIPPAGE_PERCENT: u128 = 2;
+// extra input, to cover the price moving between the quote and the swap
+const QUOTE_MARGIN_PERCENT: u128 = 5;
+
+thread_local! {
+    // pools with a swap in progress. They keep a single balance for all the bot's swaps,
+    // so one at a time, to tell which tokens left there belong to which user
+    static SWAPPING: RefCell<BTreeSet<Principal>> = RefCell::new(BTreeSet::new());
+}
+
+// released when dropped, also if the swap traps
+struct PoolGuard {
+    pool_canister_id: Principal,
+}
+
+impl PoolGuard {
+    fn acquire(
+        pool_canister_id: Principal
+    ) -> Result<Self, String> {
+        SWAPPING.with_borrow_mut(|pools| {
+            if !pools.insert(pool_canister_id) {
+                return Err("Another swap is in progress, please try again in a moment".to_string());
+            }
+
+            Ok(Self {
+                pool_canister_id,
+            })
+        })
+    }
+}
+
+impl Drop for PoolGuard {
+    fn drop(
+        &mut self
+    ) {
+        SWAPPING.with_borrow_mut(|pools| {
+            pools.remove(&self.pool_canister_id);
+        });
+    }
+}
+
+pub struct SwapService;
+
+impl SwapService {
+    pub fn set_route(
+        token: Token,
+        route: Option<SwapRoute>
+    ) -> Result<(), String> {
+        if token == Token::Icp {
+            return Err("ICP is converted to cycles by the CMC".to_string());
+        }
+
+        if let Some(route) = route {
+            SwapRouteStorage::save(token, route);
+        }
+        else {
+            SwapRouteStorage::remove(&token);
+        }
+
+        Ok(())
+    }
+
+    pub fn is_swappable(
+        token: Token
+    ) -> bool {
+        SwapRouteStorage::load(&token).is_some()
+    }
+
+    // estimates the token amount that must be swapped to receive the ICP amount (in e8s)
+    pub async fn estimate_input(
+        token: Token,
+        e8s: u64
+    ) -> Result<u128, String> {
+        let route = Self::route(token)?;
+        let unit = token.unit();
+
+        let out = IcpSwapPool::quote(&route, unit).await?;
+        if out == 0 {
+            return Err(format!("No liquidity to swap {} to ICP", token));
+        }
+
+        let fee = Icrc::fee(token.ledger_canister_id()).await?;
+        // the ICP fees of the withdrawal from the pool and of the credit to the wallet
+        let e8s = e8s as u128 + 2 * DEFAULT_FEE.e8s() as u128;
+
+        // plus the token fees of the transfer to the pool and of the deposit
+        Ok(
+            (e8s * unit).div_ceil(out) * (100 + QUOTE_MARGIN_PERCENT) / 100 + 2 * fee
+        )
+    }
+
+    // swaps the token amount from the user's wallet to ICP, credited to the user's wallet.
+    // Returns the ICP (in e8s) credited
+    pub async fn swap_to_icp(
+        user_id: Principal,
+        token: Token,
+        amount: u128
+    ) -> Result<u64, String> {
+        let route = Self::route(token)?;
+        let ledger = token.ledger_canister_id();
+        let fee = Icrc::fee(ledger).await?;
+        let icp_fee = DEFAULT_FEE.e8s() as u128;
+
+        // the transfer to the pool and the pool's deposit are paid with the amount
+        if amount <= 2 * fee {
+            return Err(format!("Amount too low. Min: {} {}", token.from_units(2 * fee + 1), token));
+        }
+
+        let _guard = PoolGuard::acquire(route.pool_canister_id)?;
+        let bot_id = ic_cdk::id();
+
+        // ICP left at the pool by a failed withdrawal, already credited to its user
+        match IcpSwapPool::unused_balance(&route, bot_id).await {
+            Ok((_, unused_out)) if unused_out > icp_fee => {
+                if let Err(err) = IcpSwapPool::withdraw(&route, MAINNET_LEDGER_CANISTER_ID, unused_out, icp_fee).await {
+                    ic_cdk::println!("error: withdrawing {} e8s left at pool {}: {}", unused_out, route.pool_canister_id, err);
+                }
+            },
+            Ok(_) => {
+            },
+            Err(err) => {
+                ic_cdk::println!("error: reading the unused balance at pool {}: {}", route.pool_canister_id, err);
+            }
+        }
+
+        // 1st: move the tokens from the user's subaccount straight to the bot's deposit account at the pool
+        let deposit = amount - fee;
+        Icrc::transfer(
+            ledger, 
+            WalletService::token_account(user_id).subaccount, 
+            IcpSwapPool::deposit_account(&route, bot_id), 
+            deposit
+        ).await?;
+
+        // 2nd: swap them
+        let out = match Self::swap(&route, ledger, deposit, fee).await {
+            Ok(out) => {
+                out
+            },
+            Err(err) => {
+                if let Err(err) = Self::refund(user_id, &route, ledger, deposit, fee).await {
+                    ic_cdk::println!(
+                        "error: could not return {} {} to user {}: {}", 
+                        deposit, token, user_id.to_text(), err
+                    );
+                }
+                return Err(format!("Swapping {} to ICP failed: {}", token, err));
+            }
+        };
+
+        // 3rd: store the ICP owed to the user before withdrawing it, so its transfer is retried if it fails.
+        // The withdrawal and the transfer cost a ledger fee each
+        let e8s = out.saturating_sub(2 * icp_fee) as u64;
+        WalletService::add_credit(user_id, PendingCredit { 
+            from: None, 
+            amount: e8s, 
+            reason: CreditReason::TokenSwap { 
+                token, 
+                token_amount: amount 
+            },
+        });
+
+        if let Err(err) = IcpSwapPool::withdraw(&route, MAINNET_LEDGER_CANISTER_ID, out, icp_fee).await {
+            // withdrawn by the next swap at the pool
+            ic_cdk::println!("error: withdrawing {} e8s from pool {}: {}", out, route.pool_canister_id, err);
+        }
+
+        WalletService::settle_credits(user_id).await;
+
+        Ok(e8s)
+    }
+
+    async fn swap(
+        route: &SwapRoute,
+        ledger: Principal,
+        amount: u128,
+        fee: u128
+    ) -> Result<u128, String> {
+        let amount_in = IcpSwapPool::deposit(route, ledger, amount, fee).await?;
+
+        let min_out = IcpSwapPool::quote(route, amount_in).await? * (100 - MAX_SLIPPAGE_PERCENT) / 100;
+
+        IcpSwapPool::swap(route, amount_in, min_out).await
+    }
+
+    // returns the tokens deposited, but not swapped, to the user's wallet. Returns the amount returned
+    async fn refund(
+        user_id: Principal,
+        route: &SwapRoute,
+        ledger: Principal,
+        deposit: u128,
+        fee: u128
+    ) -> Result<u128, String> {
+        let (unused_in, _) = IcpSwapPool::unused_balance(route, ic_cdk::id()).await?;
+        
+        // no more than this swap deposited
+        let unused_in = unused_in.min(deposit);
+        if unused_in <= 2 * fee {
+            return Err(format!(
+                "only {} left at the pool, the tokens may still be in the bot's deposit account there", 
+                unused_in
+            ));
+        }
+
+        // the withdrawal and the transfer cost a ledger fee each
+        IcpSwapPool::withdraw(route, ledger, unused_in, fee).await?;
+
+        let amount = unused_in - 2 * fee;
+        Icrc::transfer(
+            ledger, 
+            None, 
+            WalletService::token_account(user_id), 
+            amount
+        ).await?;
+
+        Ok(amount)
+    }
+
+    fn route(
+        token: Token
+    ) -> Result<SwapRoute, String> {
+        SwapRouteStorage::load(&token)
+            .ok_or(format!("{} can't be converted to ICP: no swap route configured", token))
+    }
+}
@@ -0,0 +1,3 @@
+pub mod throttle;
+
+pub use throttle::*;
\ No newline at end of file
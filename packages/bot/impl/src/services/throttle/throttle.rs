@@ -0,0 +1,158 @@
+use std::{cell::Cell, time::Duration};
+use ic_cdk_timers::TimerId;
+use oc_bots_sdk::types::Chat;
+use crate::{
+    storage::throttle::ThrottleStorage, 
+    types::{monitor::MonitorId, throttle::{ChatThrottle, RateLimit}}, 
+    utils::chat::{get_text_api_key, send_messages}
+};
+
+const MIN_DRAIN_DELAY: u64 = 5 * 1_000_000_000; // 5 seconds
+
+thread_local! {
+    static DRAIN_TIMER_ID: Cell<Option<TimerId>> = Cell::default();
+}
+
+pub struct ThrottleService;
+
+impl ThrottleService {
+    pub fn start_if_required(
+    ) {
+        Self::schedule_drain_if_required();
+    }
+
+    // new messages should be refused while posting to the chat keeps failing,
+    // so the caller can retry them later
+    pub fn check(
+        chat: Chat
+    ) -> Result<(), String> {
+        match ThrottleStorage::load(&chat.into()) {
+            Some(throttle) => match throttle.failing(ic_cdk::api::time()) {
+                Some(err) => Err(format!("Posting to the chat is failing: {}", err)),
+                None => Ok(()),
+            },
+            None => Ok(()),
+        }
+    }
+
+    // queues the messages and posts the ones the chat's rate limit allows now,
+    // the others are posted later by the drain timer
+    pub async fn post(
+        chat: Chat,
+        messages: Vec<String>
+    ) {
+        let id = MonitorId::from(chat);
+        let now = ic_cdk::api::time();
+
+        let mut throttle = ThrottleStorage::load(&id)
+            .unwrap_or_else(|| ChatThrottle::new(now));
+        throttle.push(messages);
+        let messages = throttle.take(now);
+        ThrottleStorage::save(id, throttle);
+
+        Self::send(chat, messages).await;
+        Self::schedule_drain_if_required();
+    }
+
+    pub fn get(
+        chat: Chat
+    ) -> ChatThrottle {
+        ThrottleStorage::load(&chat.into())
+            .unwrap_or_else(|| ChatThrottle::new(ic_cdk::api::time()))
+    }
+
+    pub fn set_limit(
+        chat: Chat,
+        limit: RateLimit
+    ) {
+        let mut throttle = Self::get(chat);
+        throttle.limit = Some(limit);
+        ThrottleStorage::save(chat.into(), throttle);
+
+        Self::schedule_drain_if_required();
+    }
+
+    async fn drain(
+    ) {
+        let now = ic_cdk::api::time();
+
+        for (id, mut throttle) in ThrottleStorage::with_backlog() {
+            let messages = throttle.take(now);
+            ThrottleStorage::save(id, throttle);
+
+            Self::send(id.0, messages).await;
+        }
+
+        Self::schedule_drain_if_required();
+    }
+
+    async fn send(
+        chat: Chat,
+        messages: Vec<String>
+    ) {
+        if messages.is_empty() {
+            return;
+        }
+
+        let id = MonitorId::from(chat);
+
+        let mut posted = 0;
+        let res = match get_text_api_key(chat) {
+            Some(ctx) => {
+                send_messages(ctx, chat, messages.clone(), |completed| posted = completed).await
+            },
+            None => {
+                Err("No API key with permission to send text messages to the chat".to_string())
+            }
+        };
+
+        let Some(mut throttle) = ThrottleStorage::load(&id) else {
+            return;
+        };
+
+        // what wasn't posted goes back to the front of the queue, to be retried
+        match res {
+            Ok(()) => {
+                throttle.posted();
+            },
+            Err(err) => {
+                ic_cdk::println!("error: posting to chat {}: {}", id, err);
+                let left = messages.len() - posted;
+                if throttle.requeue(messages[posted..].to_vec(), posted > 0, err, ic_cdk::api::time()) {
+                    ic_cdk::println!("error: dropped {} messages to chat {} after too many attempts", left, id);
+                }
+            }
+        }
+
+        ThrottleStorage::save(id, throttle);
+    }
+
+    fn schedule_drain_if_required(
+    ) {
+        let delay = ThrottleStorage::with_backlog().iter()
+            .map(|(_, throttle)| throttle.next_token_in())
+            .min();
+
+        if let Some(delay) = delay {
+            Self::schedule_drain(delay.max(MIN_DRAIN_DELAY));
+        }
+    }
+
+    fn schedule_drain(
+        delay: u64
+    ) {
+        if let Some(timer_id) = DRAIN_TIMER_ID.get() {
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+
+        let timer_id = ic_cdk_timers::set_timer(
+            Duration::from_nanos(delay),
+            || {
+                DRAIN_TIMER_ID.set(None);
+                ic_cdk::spawn(Self::drain());
+            }
+        );
+
+        DRAIN_TIMER_ID.set(Some(timer_id));
+    }
+}
@@ -1,14 +1,38 @@
+use std::time::Duration;
 use candid::Principal;
 use ic_ledger_types::{
     account_balance, AccountBalanceArgs, AccountIdentifier, 
     Memo, Subaccount, Timestamp, Tokens, TransferArgs, 
     DEFAULT_FEE, DEFAULT_SUBACCOUNT, MAINNET_LEDGER_CANISTER_ID
 };
-use crate::{storage::user::UserStorage, types::user::UserTransaction};
+use icrc_ledger_types::icrc1::account::Account;
+use crate::{
+    storage::user::UserStorage, 
+    types::{
+        token::Token, 
+        user::{CreditReason, PendingCredit, UserTransaction}
+    }, 
+    utils::{
+        icp_index::{IcpIndex, Operation}, 
+        icrc::Icrc
+    }
+};
+
+const CREDITS_RETRY_INTERVAL: u64 = 60 * 60; // 1 hour
+const MAX_NOTICES: usize = 10;
 
 pub struct WalletService;
 
 impl WalletService {
+    pub fn start(
+    ) {
+        // retry the credits whose transfers failed
+        ic_cdk_timers::set_timer_interval(
+            Duration::from_secs(CREDITS_RETRY_INTERVAL),
+            || ic_cdk::spawn(Self::settle_all_credits())
+        );
+    }
+
     pub fn account_id(
         user_id: Principal
     ) -> AccountIdentifier {
@@ -43,36 +67,108 @@ impl WalletService {
         Ok(icp.e8s())
     }
 
+    // the user's account at the ICRC-1 ledgers
+    pub fn token_account(
+        user_id: Principal
+    ) -> Account {
+        Account {
+            owner: ic_cdk::id(),
+            subaccount: Some(Subaccount::from(user_id).0),
+        }
+    }
+
+    pub async fn token_balance_of(
+        user_id: Principal,
+        token: Token
+    ) -> Result<u128, String> {
+        Icrc::balance_of(
+            token.ledger_canister_id(), 
+            Self::token_account(user_id)
+        ).await
+    }
+
+    pub async fn withdraw_token(
+        user_id: Principal,
+        token: Token,
+        to: Option<String>,
+        amount: u128
+    ) -> Result<(u64, String), String> {
+        let to = if let Some(to) = to {
+            to.parse::<Account>()
+                .map_err(|e| format!("Invalid account {}: {}", to, e))?
+        }
+        else {
+            Account {
+                owner: user_id,
+                subaccount: None,
+            }
+        };
+
+        let block_num = Icrc::transfer(
+            token.ledger_canister_id(), 
+            Some(Subaccount::from(user_id).0), 
+            to, 
+            amount
+        ).await?;
+
+        Self::add_tx(user_id, UserTransaction::TokenWithdraw { 
+            token, 
+            amount, 
+            to: to.to_string(), 
+            block_num, 
+            timestamp: (ic_cdk::api::time() / 1_000_000_000) as _,
+        });
+
+        Ok((block_num, to.to_string()))
+    }
+
     pub async fn transfer_hex(
         user_id: Principal, 
         to: Option<String>, 
         amount: u64
     ) -> Result<(u64, String), String> {
         let to = if let Some(to) = to {
-            AccountIdentifier::from_hex(&to).unwrap()
+            AccountIdentifier::from_hex(&to)
+                .map_err(|e| format!("Invalid account id {}: {}", to, e))?
         }
         else {
             AccountIdentifier::new(&user_id, &DEFAULT_SUBACCOUNT)
         };
         
-        Self::transfer(Some(user_id), to, amount).await
+        let (block_num, to_hex) = Self::transfer(Some(user_id), to, amount).await?;
+
+        Self::add_tx(user_id, UserTransaction::IcpWithdraw { 
+            amount,
+            to, 
+            block_num, 
+            timestamp: (ic_cdk::api::time() / 1_000_000_000) as _,
+        });
+
+        Ok((block_num, to_hex))
     }
 
     pub async fn transfer(
         from: Option<Principal>, 
         to: AccountIdentifier, 
         amount: u64
+    ) -> Result<(u64, String), String> {
+        Self::transfer_from_subaccount(
+            from.map(Subaccount::from), 
+            to, 
+            amount
+        ).await
+    }
+
+    async fn transfer_from_subaccount(
+        from_subaccount: Option<Subaccount>, 
+        to: AccountIdentifier, 
+        amount: u64
     ) -> Result<(u64, String), String> {
         let now = ic_cdk::api::time();
         let block_num = ic_ledger_types::transfer(
             MAINNET_LEDGER_CANISTER_ID, 
             TransferArgs { 
-                from_subaccount: if let Some(from) = from {
-                    Some(from.into())
-                } 
-                else {
-                    None
-                },  
+                from_subaccount,  
                 to, 
                 fee: DEFAULT_FEE.into(), 
                 created_at_time: Some(Timestamp{timestamp_nanos: now}), 
@@ -83,20 +179,167 @@ impl WalletService {
             .map_err(|e| e.1)?
             .map_err(|e| e.to_string())?;
 
-        if let Some(from) = from {
-            let mut user = UserStorage::load(&from);
-            user.txs.push(UserTransaction::IcpWithdraw { 
-                amount,
-                to, 
-                block_num, 
-                timestamp: (now / 1_000_000_000) as _,
-            });
-            UserStorage::save(from, user);
-        }
-
         Ok((
             block_num,
             to.to_hex()
         ))
     }
+
+    pub fn add_tx(
+        user_id: Principal,
+        tx: UserTransaction
+    ) {
+        let mut user = UserStorage::load(&user_id);
+        user.txs.push(tx);
+        UserStorage::save(user_id, user);
+    }
+
+    // keeps the alert until the user reads it with take_notices()
+    pub fn add_notice(
+        user_id: Principal,
+        text: String
+    ) {
+        let mut user = UserStorage::load(&user_id);
+        let notices = user.notices.get_or_insert_with(Vec::new);
+        notices.push(((ic_cdk::api::time() / 1_000_000_000) as _, text));
+        if notices.len() > MAX_NOTICES {
+            notices.drain(..notices.len() - MAX_NOTICES);
+        }
+        UserStorage::save(user_id, user);
+    }
+
+    pub fn take_notices(
+        user_id: Principal
+    ) -> Vec<(u32, String)> {
+        let mut user = UserStorage::load(&user_id);
+        let Some(notices) = user.notices.take() else {
+            return vec![];
+        };
+        UserStorage::save(user_id, user);
+        notices
+    }
+
+    // stores the credit, to be transferred by the next settle_credits()
+    pub fn add_credit(
+        user_id: Principal,
+        credit: PendingCredit
+    ) {
+        let mut user = UserStorage::load(&user_id);
+        user.pending_credits.get_or_insert_with(Vec::new).push(credit);
+        UserStorage::save(user_id, user);
+    }
+
+    // transfers the ICP owed to the user, keeping the credits that failed to be retried later
+    pub async fn settle_credits(
+        user_id: Principal
+    ) {
+        // take them out, so concurrent calls don't transfer them twice
+        let mut user = UserStorage::load(&user_id);
+        let Some(credits) = user.pending_credits.take() else {
+            return;
+        };
+        UserStorage::save(user_id, user);
+
+        let mut failed = vec![];
+        for credit in credits {
+            match Self::transfer_from_subaccount(
+                credit.from, 
+                Self::account_id(user_id), 
+                credit.amount
+            ).await {
+                Ok((block_num, _)) => {
+                    let timestamp = (ic_cdk::api::time() / 1_000_000_000) as _;
+                    Self::add_tx(user_id, match credit.reason {
+                        CreditReason::MonitorDeleted { canister_id, cycles } => {
+                            UserTransaction::MonitorRefund { 
+                                canister_id, 
+                                cycles, 
+                                amount: credit.amount, 
+                                block_num, 
+                                timestamp, 
+                            }
+                        },
+                        CreditReason::TokenSwap { token, token_amount } => {
+                            UserTransaction::TokenSwap { 
+                                token, 
+                                token_amount, 
+                                amount: credit.amount, 
+                                timestamp, 
+                            }
+                        },
+                    });
+                },
+                Err(err) => {
+                    ic_cdk::println!(
+                        "error: crediting {} e8s to user {}: {}", 
+                        credit.amount, 
+                        user_id.to_text(), 
+                        err
+                    );
+                    failed.push(credit);
+                }
+            }
+        }
+
+        if !failed.is_empty() {
+            let mut user = UserStorage::load(&user_id);
+            user.pending_credits.get_or_insert_with(Vec::new).extend(failed);
+            UserStorage::save(user_id, user);
+        }
+    }
+
+    async fn settle_all_credits(
+    ) {
+        for user_id in UserStorage::with_pending_credits() {
+            Self::settle_credits(user_id).await;
+        }
+    }
+
+    // records the ICP transferred to the user's account since the last scan. Returns the balance
+    pub async fn sync_deposits(
+        user_id: Principal
+    ) -> Result<u64, String> {
+        // transfer what the user is still owed first, so it's included in the balance
+        Self::settle_credits(user_id).await;
+
+        let acc_id = Self::account_id(user_id);
+        // refunds are sent from the bot's account and recorded when issued
+        let bot_acc_id = AccountIdentifier::new(&ic_cdk::id(), &DEFAULT_SUBACCOUNT).to_hex();
+
+        let last_scanned_tx = UserStorage::load(&user_id).last_scanned_tx;
+        let (txs, balance) = IcpIndex::transactions_since(acc_id, last_scanned_tx).await?;
+
+        // reload it, as it could have changed while waiting
+        let mut user = UserStorage::load(&user_id);
+        // an overlapping call could have recorded some of them already
+        let scanned = user.last_scanned_tx;
+        if let Some(last) = txs.first() {
+            user.last_scanned_tx = Some(scanned.map_or(last.id, |id| id.max(last.id)));
+        }
+
+        for tx in txs.into_iter().rev() {
+            if scanned.is_some_and(|id| tx.id <= id) {
+                continue;
+            }
+
+            if let Operation::Transfer { from, to, amount, .. } = tx.transaction.operation {
+                if to != acc_id.to_hex() || from == bot_acc_id {
+                    continue;
+                }
+
+                user.txs.push(UserTransaction::IcpDeposit { 
+                    amount: amount.e8s(), 
+                    from: AccountIdentifier::from_hex(&from)?, 
+                    block_num: tx.id, 
+                    timestamp: tx.transaction.timestamp
+                        .map(|t| t.timestamp_nanos / 1_000_000_000)
+                        .unwrap_or_default() as _,
+                });
+            }
+        }
+
+        UserStorage::save(user_id, user);
+
+        Ok(balance)
+    }
 }
\ No newline at end of file
@@ -1,2 +1,6 @@
 pub mod monitor;
-pub mod user;
\ No newline at end of file
+pub mod user;
+pub mod throttle;
+pub mod permission;
+pub mod swap_route;
+pub mod top_up;
\ No newline at end of file
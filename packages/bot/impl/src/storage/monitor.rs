@@ -35,6 +35,22 @@ impl MonitorStorage {
         });
     }
 
+    pub fn remove(
+        id: &MonitorId
+    ) -> Option<Monitor> {
+        let monitor = MONITORS.with_borrow_mut(|monitors| {
+            monitors.remove(id)
+        });
+
+        if let Some(monitor) = &monitor {
+            CAN_TO_MON_ID.with_borrow_mut(|dic| {
+                dic.remove(&monitor.canister_id)
+            });
+        }
+
+        monitor
+    }
+
     pub fn load(
         id: &MonitorId
     ) -> Option<Monitor> {
@@ -0,0 +1,37 @@
+use std::cell::RefCell;
+use ic_stable_structures::BTreeMap;
+use crate::{
+    memory::{get_permissions_memory, Memory}, 
+    types::{monitor::MonitorId, permission::ChatPermissions}
+};
+
+pub struct PermissionStorage;
+
+thread_local! {
+    // keyed by chat
+    static PERMISSIONS: RefCell<BTreeMap<MonitorId, ChatPermissions, Memory>> = RefCell::new(
+        BTreeMap::init(
+            get_permissions_memory()
+        )
+    );
+}
+
+impl PermissionStorage {
+    pub fn save(
+        id: MonitorId,
+        permissions: ChatPermissions
+    ) {
+        PERMISSIONS.with_borrow_mut(|map| {
+            map.insert(id, permissions)
+        });
+    }
+
+    pub fn load(
+        id: &MonitorId
+    ) -> ChatPermissions {
+        PERMISSIONS.with_borrow(|permissions| {
+            permissions.get(id)
+                .unwrap_or_default()
+        })
+    }
+}
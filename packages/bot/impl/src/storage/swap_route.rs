@@ -0,0 +1,43 @@
+use std::cell::RefCell;
+use ic_stable_structures::BTreeMap;
+use crate::{
+    memory::{get_swap_routes_memory, Memory}, 
+    types::token::{SwapRoute, Token}
+};
+
+pub struct SwapRouteStorage;
+
+thread_local! {
+    static SWAP_ROUTES: RefCell<BTreeMap<Token, SwapRoute, Memory>> = RefCell::new(
+        BTreeMap::init(
+            get_swap_routes_memory()
+        )
+    );
+}
+
+impl SwapRouteStorage {
+    pub fn save(
+        token: Token,
+        route: SwapRoute
+    ) {
+        SWAP_ROUTES.with_borrow_mut(|routes| {
+            routes.insert(token, route)
+        });
+    }
+
+    pub fn remove(
+        token: &Token
+    ) {
+        SWAP_ROUTES.with_borrow_mut(|routes| {
+            routes.remove(token)
+        });
+    }
+
+    pub fn load(
+        token: &Token
+    ) -> Option<SwapRoute> {
+        SWAP_ROUTES.with_borrow(|routes| {
+            routes.get(token)
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use std::cell::RefCell;
+use ic_stable_structures::BTreeMap;
+use crate::{
+    memory::{get_throttles_memory, Memory}, 
+    types::{monitor::MonitorId, throttle::ChatThrottle}
+};
+
+pub struct ThrottleStorage;
+
+thread_local! {
+    // keyed by the chat where the events are posted
+    static THROTTLES: RefCell<BTreeMap<MonitorId, ChatThrottle, Memory>> = RefCell::new(
+        BTreeMap::init(
+            get_throttles_memory()
+        )
+    );
+}
+
+impl ThrottleStorage {
+    pub fn save(
+        id: MonitorId,
+        throttle: ChatThrottle
+    ) {
+        THROTTLES.with_borrow_mut(|throttles| {
+            throttles.insert(id, throttle)
+        });
+    }
+
+    pub fn load(
+        id: &MonitorId
+    ) -> Option<ChatThrottle> {
+        THROTTLES.with_borrow(|throttles| {
+            throttles.get(id)
+        })
+    }
+
+    pub fn with_backlog(
+    ) -> Vec<(MonitorId, ChatThrottle)> {
+        THROTTLES.with_borrow(|throttles| {
+            throttles.iter()
+                .filter(|(_, throttle)| throttle.has_backlog())
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+use candid::Principal;
+use ic_stable_structures::BTreeMap;
+use crate::{
+    memory::{get_pending_top_ups_memory, Memory}, 
+    types::funding::PendingTopUp
+};
+
+pub struct PendingTopUpStorage;
+
+thread_local! {
+    // at most one per canister, as no other top-up is paid while it's pending
+    static PENDING_TOP_UPS: RefCell<BTreeMap<Principal, PendingTopUp, Memory>> = RefCell::new(
+        BTreeMap::init(
+            get_pending_top_ups_memory()
+        )
+    );
+}
+
+impl PendingTopUpStorage {
+    pub fn save(
+        canister_id: Principal,
+        top_up: PendingTopUp
+    ) {
+        PENDING_TOP_UPS.with_borrow_mut(|top_ups| {
+            top_ups.insert(canister_id, top_up)
+        });
+    }
+
+    pub fn remove(
+        canister_id: &Principal
+    ) -> Option<PendingTopUp> {
+        PENDING_TOP_UPS.with_borrow_mut(|top_ups| {
+            top_ups.remove(canister_id)
+        })
+    }
+
+    pub fn load(
+        canister_id: &Principal
+    ) -> Option<PendingTopUp> {
+        PENDING_TOP_UPS.with_borrow(|top_ups| {
+            top_ups.get(canister_id)
+        })
+    }
+
+    pub fn canister_ids(
+    ) -> Vec<Principal> {
+        PENDING_TOP_UPS.with_borrow(|top_ups| {
+            top_ups.iter()
+                .map(|(id, _)| id)
+                .collect()
+        })
+    }
+}
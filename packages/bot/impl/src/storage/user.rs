@@ -34,4 +34,15 @@ impl UserStorage {
                 .unwrap_or_default()
         })
     }
+
+    // the users still owed some ICP
+    pub fn with_pending_credits(
+    ) -> Vec<UserId> {
+        USERS.with_borrow(|users| {
+            users.iter()
+                .filter(|(_, user)| user.pending_credits.as_ref().is_some_and(|credits| !credits.is_empty()))
+                .map(|(id, _)| id)
+                .collect()
+        })
+    }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use monitor_api::updates::add_job::JobId;
+use super::{permission::{Action, Role}, token::Token};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -16,13 +17,105 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     #[command(about = "Deploy a event monitor canister for this channel/group")]
-    Deploy,
+    Deploy {
+        #[arg(short, long, value_enum, ignore_case = true, default_value_t = Token::Icp, help = "Token to pay with, swapped to ICP if your ICP balance is too low")]
+        token: Token,
+    },
     #[command(about = "Print the status of this channel/group's event monitor canister")]
     Status,
     #[command(subcommand, about = "Job sub-commands")]
     Job (Job),
     #[command(subcommand, about = "EventMon Wallet sub-commands")]
     Wallet (Wallet),
+    #[command(subcommand, about = "This chat's settings")]
+    Config (Config),
+    #[command(subcommand, about = "Monitor sub-commands")]
+    Monitor (Monitor),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Monitor {
+    #[command(about = "Stop this chat's monitor canister. Its jobs won't run until it's started again")]
+    Stop,
+    #[command(about = "Start this chat's monitor canister")]
+    Start,
+    #[command(about = "Upgrade this chat's monitor canister to the latest version")]
+    Upgrade,
+    #[command(about = "Delete this chat's monitor canister, withdrawing its remaining cycles. Can't be undone")]
+    Delete,
+    #[command(about = "Transfer the ownership of this chat's monitor to another user. The user must accept it")]
+    TransferOwner {
+        #[arg(help = "User id")]
+        user_id: String,
+    },
+    #[command(about = "Invite a user to help fund this chat's monitor from their wallet. The user must accept it")]
+    FundFrom {
+        #[arg(help = "User id")]
+        user_id: String,
+    },
+    #[command(about = "Accept the ownership or the funding of this chat's monitor offered to you")]
+    Accept,
+    #[command(about = "Stop funding this chat's monitor from a user's wallet")]
+    RemoveFunder {
+        #[arg(help = "User id")]
+        user_id: String,
+    },
+    #[command(subcommand, about = "Cycles top-up policy sub-commands")]
+    Funding (Funding),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Funding {
+    #[command(about = "Show this chat's monitor top-up policy")]
+    Show,
+    #[command(about = "Change this chat's monitor top-up policy")]
+    Set {
+        #[command(flatten)]
+        changes: FundingChanges,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct FundingChanges {
+    #[arg(long, help = "Enable or disable the automatic top-ups (true or false)")]
+    pub enabled: Option<bool>,
+    #[arg(long, help = "Top-up when the cycles available are below this, in trillions of cycles (ie: 0.5)")]
+    pub min_cycles: Option<String>,
+    #[arg(long, help = "Cycles added on each top-up, in trillions of cycles (ie: 0.1)")]
+    pub top_up: Option<String>,
+    #[arg(long, help = "Max ICP spent on top-ups per 30 days, in decimal format (ie: 1.25), 0 to remove the limit")]
+    pub max_icp: Option<String>,
+    #[arg(long, value_enum, ignore_case = true, help = "Token of the owner's wallet swapped to ICP when the funders run out of ICP (ICP to disable it)")]
+    pub pay_with: Option<Token>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Config {
+    #[command(about = "Show this chat's settings")]
+    Show,
+    #[command(about = "Limit how many events are posted per minute in this chat. Excess events are queued")]
+    RateLimit {
+        #[arg(help = "Events per minute, 0 to disable the limit")]
+        per_minute: u32,
+        #[arg(short, long, help = "Max events posted at once (default: the events per minute)")]
+        burst: Option<u32>,
+        #[arg(short, long, help = "Max events waiting to be posted, before they are suppressed (default: 100)")]
+        queue: Option<u32>,
+    },
+    #[command(about = "Grant a role in this chat to a user (member removes it)")]
+    Role {
+        #[arg(help = "User id")]
+        user_id: String,
+        #[arg(value_enum, help = "Role")]
+        role: Role,
+    },
+    #[command(about = "Set the minimum role required for an action (default: admin)")]
+    Permission {
+        #[arg(value_enum, help = "Action")]
+        action: Action,
+        #[arg(value_enum, help = "Minimum role")]
+        role: Role,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -34,6 +127,25 @@ pub enum Job {
         #[arg(default_value_t = 1, help = "Optional page number (default = 1)")]
         page: u32,
     },
+    #[command(about = "Show the details of a job")]
+    Show {
+        #[arg(help = "Job id")]
+        id: JobId
+    },
+    #[command(about = "Display the run history of a job")]
+    Logs {
+        #[arg(help = "Job id")]
+        id: JobId,
+        #[arg(default_value_t = 1, help = "Optional page number (default = 1)")]
+        page: usize,
+    },
+    #[command(about = "Move the position of a job")]
+    Seek {
+        #[arg(help = "Job id")]
+        id: JobId,
+        #[arg(allow_hyphen_values = true, help = "Absolute position, \"latest\" or -N to go back N events")]
+        position: String,
+    },
     #[command(about = "Start a job")]
     Start {
         #[arg(help = "Job id")]
@@ -44,11 +156,58 @@ pub enum Job {
         #[arg(help = "Job id")]
         id: JobId
     },
+    #[command(about = "Edit a job")]
+    Edit {
+        #[arg(help = "Job id")]
+        id: JobId,
+        #[command(flatten)]
+        changes: JobChanges,
+    },
     #[command(about = "Delete a job")]
     Delete {
         #[arg(help = "Job id")]
         id: JobId
     },
+    #[command(subcommand, about = "Outbox sub-commands (messages waiting to be delivered)")]
+    Outbox(Outbox),
+}
+
+#[derive(Args, Debug)]
+pub struct JobChanges {
+    #[arg(short, long, help = "New interval, in seconds")]
+    pub interval: Option<u32>,
+    #[arg(short, long, help = "New output template")]
+    pub template: Option<String>,
+    #[arg(short, long, help = "New max number of items to retrieve per call")]
+    pub batch_size: Option<u32>,
+    #[arg(short, long, help = "New filter expression (\"\" to remove it)")]
+    pub filter: Option<String>,
+    #[arg(short, long, help = "New number of consecutive failures before pausing the job, 0 to never pause")]
+    pub max_failures: Option<u32>,
+    #[arg(long, help = "New destination: a channel id of this community, a group id, <community id>/<channel id> or \"here\"")]
+    pub to: Option<String>,
+    #[command(flatten)]
+    pub delivery: DeliveryOptions,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Outbox {
+    #[command(about = "List the messages waiting to be delivered")]
+    List {
+        #[arg(default_value_t = 1, help = "Optional page number (default = 1)")]
+        page: u32,
+        #[arg(short, long, help = "List the messages that failed too many times instead")]
+        dead: bool,
+    },
+    #[command(about = "Retry the delivery of all pending and failed messages now")]
+    Flush,
+    #[command(about = "Drop messages from the outbox")]
+    Drop {
+        #[arg(help = "Item id or \"all\"")]
+        id: String,
+        #[arg(short, long, help = "Drop from the messages that failed too many times instead")]
+        dead: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -61,27 +220,112 @@ pub enum CreateSubcommand {
         method_name: String,
         #[arg(help = "Interval, in seconds, to poll the canister")]
         interval: u32,
-        #[arg(help = "Output template")]
+        #[arg(help = "Output template. Fields: {field.sub}, filters: {field | upper}, blocks: {#if field}..{#else}..{/if}, {#each list as item}..{/each}")]
+        output_template: String,
+        #[command(flatten)]
+        options: JobOptions,
+    },
+    #[command(about = "Create a new job to monitor the blocks of an ICRC-3 ledger")]
+    Icrc3 {
+        #[arg(help = "Ledger canister id")]
+        canister_id: String,
+        #[arg(help = "Interval, in seconds, to poll the ledger")]
+        interval: u32,
+        #[arg(help = "Output template. Block fields: {id}, {btype}, {ts}, {op}, {from}, {to}, {amt}, {fee}, {memo}, {spender}, {tx.*}")]
         output_template: String,
-        #[arg(short, long, default_value_t = 4, help = "Max number of items to retrieve per call")]
-        batch_size: u32,
+        #[command(flatten)]
+        options: JobOptions,
     },
+    #[command(about = "Create a new job to monitor the blocks of the ICP ledger")]
+    Icp {
+        #[arg(help = "Interval, in seconds, to poll the ledger")]
+        interval: u32,
+        #[arg(help = "Output template. Block fields: {index}, {timestamp}, {op}, {from}, {to}, {spender}, {amount}, {fee}, {memo}")]
+        output_template: String,
+        #[arg(short, long = "account", help = "Only post blocks touching this account id, in hex format (can be repeated)")]
+        accounts: Vec<String>,
+        #[arg(short, long, help = "Optional ledger canister id (default: ICP ledger)")]
+        ledger: Option<String>,
+        #[command(flatten)]
+        options: JobOptions,
+    },
+    #[command(about = "Create a new job to monitor the proposals of a SNS")]
+    Sns {
+        #[arg(help = "SNS governance canister id")]
+        governance_canister_id: String,
+        #[arg(help = "Interval, in seconds, to poll the governance canister")]
+        interval: u32,
+        #[arg(help = "Output template. Proposal fields: {event} (created, adopted, rejected, executed or failed), {is_<event>}, {id}, {title}, {summary}, {url}, {proposer}, {action}, {yes}, {no}, {total}, {created_at}, {decided_at}")]
+        output_template: String,
+        #[command(flatten)]
+        options: JobOptions,
+    },
+    #[command(about = "Create a new job to monitor the NNS proposals")]
+    Nns {
+        #[arg(help = "Interval, in seconds, to poll the governance canister")]
+        interval: u32,
+        #[arg(help = "Output template. Proposal fields: {event} (created, adopted, rejected, executed or failed), {is_<event>}, {id}, {title}, {summary}, {url}, {proposer}, {topic}, {yes}, {no}, {total}, {created_at}, {decided_at}")]
+        output_template: String,
+        #[arg(short, long = "topic", help = "Only post proposals of this topic, ie: Governance (can be repeated)")]
+        topics: Vec<String>,
+        #[arg(short, long = "exclude-status", help = "Don't post proposals reaching this status: Open, Rejected, Adopted, Executed or Failed (can be repeated)")]
+        excluded_statuses: Vec<String>,
+        #[arg(short, long, help = "Optional governance canister id (default: NNS governance)")]
+        governance: Option<String>,
+        #[command(flatten)]
+        options: JobOptions,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct JobOptions {
+    #[arg(short, long, default_value_t = 4, help = "Max number of items to retrieve per call")]
+    pub batch_size: u32,
+    #[arg(short, long, help = "Only post events matching this expression, ie: 'amount > 100_000_000 && op == \"transfer\"'. Operators: == != > >= < <= && || !")]
+    pub filter: Option<String>,
+    #[arg(long, default_value = "latest", help = "Where to start from: start, latest or an absolute position")]
+    pub from: String,
+    #[arg(short, long, help = "Pause the job after this many consecutive failures, 0 to never pause (default: 10)")]
+    pub max_failures: Option<u32>,
+    #[arg(long, help = "Post to another chat: a channel id of this community, a group id or <community id>/<channel id> (default: this chat)")]
+    pub to: Option<String>,
+    #[command(flatten)]
+    pub delivery: DeliveryOptions,
+}
+
+#[derive(Args, Debug)]
+pub struct DeliveryOptions {
+    #[arg(short, long, help = "How events are posted: immediate, digest:<minutes> or threshold:<number of events> (default: immediate)")]
+    pub delivery: Option<String>,
+    #[arg(long, help = "Digest header template. Variables: {count}, {since}, {until}, {job_id}, ie: '**{count} new events**'")]
+    pub header: Option<String>,
+    #[arg(long, help = "Digest footer template. Same variables as the header")]
+    pub footer: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Wallet {
-    #[command(about = "Display your ICP balance in the EventMon Wallet")]
+    #[command(about = "Display your balances in the EventMon Wallet, and the funding alerts not read yet")]
     Balance,
-    #[command(about = "Display your ICP address in the EventMon Wallet")]
+    #[command(about = "Display your addresses in the EventMon Wallet")]
     Address,
-    #[command(about = "Withdraw ICP from your account in the EventMon Wallet")]
+    #[command(about = "Withdraw tokens from your account in the EventMon Wallet")]
     Withdraw {
         #[arg(help = "Amount to withdraw in decimal format (ie: 1.25)")]
-        amount: f32,
-        #[arg(help = "Optional destination account address in hex format (default: your OC wallet)")]
+        amount: String,
+        #[arg(help = "Optional destination account: a hex address for ICP or an ICRC-1 account for other tokens (default: your OC wallet)")]
         to: Option<String>,
+        #[arg(short, long, value_enum, ignore_case = true, default_value_t = Token::Icp, help = "Token to withdraw")]
+        token: Token,
+    },
+    #[command(about = "Swap tokens in your account in the EventMon Wallet to ICP")]
+    Swap {
+        #[arg(help = "Amount to swap in decimal format (ie: 1.25)")]
+        amount: String,
+        #[arg(value_enum, ignore_case = true, help = "Token to swap")]
+        token: Token,
     },
-    #[command(about = "Display logs of ICP transactions")]
+    #[command(about = "Display logs of transactions")]
     Logs {
         #[arg(default_value_t = 1, help = "Optional page number (default = 1)")]
         page: usize,
@@ -0,0 +1,59 @@
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Encode};
+use ic_ledger_types::Subaccount;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+use crate::services::monitor::{FUND_MONITOR_CYCLES, MIN_MONITOR_CYCLES};
+use super::token::Token;
+
+pub const FUNDING_PERIOD: u64 = 30 * 24 * 60 * 60 * 1_000_000_000; // 30 days, in nanoseconds
+
+#[derive(Clone, Debug, Serialize, Deserialize, CandidType)]
+pub struct FundingPolicy {
+    pub enabled: bool,
+    // top-up when the canister's balance is below this
+    pub min_cycles: u128,
+    pub fund_cycles: u128,
+    // max ICP (in e8s) spent on top-ups per funding period
+    pub max_icp: Option<u64>,
+    // token swapped to ICP from the owner's wallet when the funders run out of ICP
+    pub pay_with: Option<Token>,
+}
+
+impl Default for FundingPolicy {
+    fn default(
+    ) -> Self {
+        Self {
+            enabled: true,
+            min_cycles: MIN_MONITOR_CYCLES,
+            fund_cycles: FUND_MONITOR_CYCLES,
+            max_icp: None,
+            pay_with: None,
+        }
+    }
+}
+
+// a top-up whose ICP is at the CMC, but whose cycles weren't minted yet
+#[derive(Clone, Debug, Serialize, Deserialize, CandidType)]
+pub struct PendingTopUp {
+    pub from: Subaccount,
+    pub block_index: u64,
+    // transferred to the CMC, without the ledger fee
+    pub e8s: u64,
+}
+
+impl Storable for PendingTopUp {
+    fn to_bytes(
+        &self
+    ) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(
+        bytes: Cow<[u8]>
+    ) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
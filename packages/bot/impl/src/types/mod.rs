@@ -1,3 +1,7 @@
 pub mod cli;
 pub mod monitor;
-pub mod user;
\ No newline at end of file
+pub mod user;
+pub mod throttle;
+pub mod permission;
+pub mod funding;
+pub mod token;
\ No newline at end of file
@@ -4,6 +4,7 @@ use ic_stable_structures::{storable::Bound, Storable};
 use monitor_api::updates::add_job::JobId;
 use oc_bots_sdk::types::Chat;
 use serde::{Deserialize, Serialize};
+use super::funding::{FundingPolicy, FUNDING_PERIOD};
 
 #[derive(Serialize, Deserialize, CandidType, Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct MonitorId(pub Chat);
@@ -71,7 +72,9 @@ impl Storable for MonitorId {
 #[derive(Clone, Serialize, Deserialize, CandidType)]
 pub enum MonitorState {
     Idle,
-    Running
+    Running,
+    // stopped by the chat, not restarted by the bot
+    Stopped,
 }
 
 impl Display for MonitorState {
@@ -82,6 +85,7 @@ impl Display for MonitorState {
         fmt.write_fmt(format_args!("{}", match self {
             MonitorState::Idle => "idle",
             MonitorState::Running => "running",
+            MonitorState::Stopped => "stopped",
         }))
     }
 }
@@ -93,7 +97,21 @@ pub struct Monitor {
     pub owner: Principal,
     pub canister_id: Principal,
     pub wasm_hash: Vec<u8>,
-    pub jobs: Vec<JobId>
+    pub jobs: Vec<JobId>,
+    // (job id, event index) of the last events posted, oldest first
+    pub delivered: Option<Vec<(JobId, u64)>>,
+    // co-funders, tried in order after the owner to top-up the canister
+    pub funders: Option<Vec<Principal>>,
+    // waiting to be accepted by the receiving user
+    pub pending_owner: Option<Principal>,
+    pub pending_funders: Option<Vec<Principal>>,
+    pub funding: Option<FundingPolicy>,
+    // (timestamp, ICP in e8s) of the top-ups done in the last funding period
+    pub funding_spent: Option<Vec<(u64, u64)>>,
+    // last time the owner was warned about the funding
+    pub funding_alerted_at: Option<u64>,
+    // (timestamp, cycles) of the last balance check, to estimate the cycles burned
+    pub cycles_sample: Option<(u64, u128)>,
 }
 
 impl Monitor {
@@ -110,8 +128,55 @@ impl Monitor {
             canister_id,
             wasm_hash,
             jobs: vec![],
+            delivered: None,
+            funders: None,
+            pending_owner: None,
+            pending_funders: None,
+            funding: None,
+            funding_spent: None,
+            funding_alerted_at: None,
+            cycles_sample: None,
         }
     }
+
+    pub fn funding(
+        &self
+    ) -> FundingPolicy {
+        self.funding.clone().unwrap_or_default()
+    }
+
+    // ICP (in e8s) spent on top-ups in the funding period ending now
+    pub fn funding_spent(
+        &self,
+        now: u64
+    ) -> u64 {
+        self.funding_spent.iter()
+            .flatten()
+            .filter(|(timestamp, _)| timestamp + FUNDING_PERIOD > now)
+            .map(|(_, e8s)| e8s)
+            .sum()
+    }
+
+    pub fn add_funding_spent(
+        &mut self,
+        e8s: u64,
+        now: u64
+    ) {
+        let spent = self.funding_spent.get_or_insert_with(Vec::new);
+        spent.retain(|(timestamp, _)| timestamp + FUNDING_PERIOD > now);
+        spent.push((now, e8s));
+    }
+
+    // the owner first, then the co-funders
+    pub fn funders(
+        &self
+    ) -> Vec<Principal> {
+        let mut funders = vec![self.owner];
+        if let Some(others) = &self.funders {
+            funders.extend(others.iter().filter(|user| **user != self.owner));
+        }
+        funders
+    }
 }
 
 impl Storable for Monitor {
@@ -137,4 +202,8 @@ pub struct MonitorStatus {
     pub memory_size: u128,
     pub cycles: u128,
     pub idle_cycles_burned_per_day: u128,
+    pub owner: Principal,
+    pub funders: Vec<Principal>,
+    pub pending_owner: Option<Principal>,
+    pub pending_funders: Vec<Principal>,
 }
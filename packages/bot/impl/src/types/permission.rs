@@ -0,0 +1,275 @@
+use std::{borrow::Cow, collections::BTreeMap, fmt::Display};
+use candid::{CandidType, Decode, Encode};
+use clap::ValueEnum;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+use super::user::UserId;
+
+// roles granted in the bot, as OpenChat doesn't tell it the role of who runs a command
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, CandidType, ValueEnum)]
+pub enum Role {
+    Member,
+    Moderator,
+    Admin,
+}
+
+impl Display for Role {
+    fn fmt(
+        &self, 
+        fmt: &mut std::fmt::Formatter<'_>
+    ) -> std::fmt::Result {
+        fmt.write_str(match self {
+            Role::Member => "member",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, CandidType, ValueEnum)]
+pub enum Action {
+    // deploy the monitor
+    Deploy,
+    // create, edit, seek and delete jobs, manage the outbox
+    EditJobs,
+    StartStopJobs,
+    // change this chat's settings
+    Config,
+}
+
+impl Display for Action {
+    fn fmt(
+        &self, 
+        fmt: &mut std::fmt::Formatter<'_>
+    ) -> std::fmt::Result {
+        fmt.write_str(match self {
+            Action::Deploy => "deploy the monitor",
+            Action::EditJobs => "edit jobs",
+            Action::StartStopJobs => "start or stop jobs",
+            Action::Config => "change the settings",
+        })
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, CandidType)]
+pub struct ChatPermissions {
+    pub roles: BTreeMap<UserId, Role>,
+    // the minimum role required per action, Admin if not set
+    pub required: BTreeMap<Action, Role>,
+}
+
+impl ChatPermissions {
+    pub fn role_of(
+        &self,
+        user_id: &UserId
+    ) -> Role {
+        self.roles.get(user_id)
+            .cloned()
+            .unwrap_or(Role::Member)
+    }
+
+    pub fn required(
+        &self,
+        action: Action
+    ) -> Role {
+        self.required.get(&action)
+            .cloned()
+            .unwrap_or(Role::Admin)
+    }
+
+    // owner: the owner of the chat's monitor, None if it has none yet
+    pub fn authorize(
+        &self,
+        owner: Option<UserId>,
+        user_id: UserId,
+        action: Action
+    ) -> Result<(), String> {
+        match owner {
+            // the monitor's owner can always manage it
+            Some(owner) if owner == user_id => {
+                return Ok(());
+            },
+            // OpenChat only lets the chat's admins run the command, so in a chat without a monitor 
+            // and roles yet they can deploy it or grant the first roles, and nothing else
+            None if self.roles.is_empty() && matches!(action, Action::Deploy | Action::Config) => {
+                return Ok(());
+            },
+            _ => {}
+        }
+
+        let required = self.required(action);
+        if self.role_of(&user_id) >= required {
+            Ok(())
+        }
+        else {
+            Err(format!(
+                "Only the monitor's owner or users with the {} role or higher can {}. The owner can grant roles with `/eventmon config role`", 
+                required, action
+            ))
+        }
+    }
+
+    // nobody but the monitor's owner can grant, or take away, a role higher than their own
+    pub fn grant(
+        &mut self,
+        owner: Option<UserId>,
+        caller: UserId,
+        user_id: UserId,
+        role: Role
+    ) -> Result<(), String> {
+        let caller_role = match owner {
+            Some(owner) if owner == caller => {
+                None
+            },
+            // the chat's admin granting the first roles, see authorize()
+            None if self.roles.is_empty() => {
+                Some(Role::Admin)
+            },
+            _ => {
+                Some(self.role_of(&caller))
+            }
+        };
+
+        if let Some(caller_role) = caller_role {
+            if role > caller_role || self.role_of(&user_id) > caller_role {
+                return Err(format!(
+                    "Only the monitor's owner can grant or change roles higher than your own ({})", 
+                    caller_role
+                ));
+            }
+        }
+
+        if role == Role::Member {
+            self.roles.remove(&user_id);
+        }
+        else {
+            self.roles.insert(user_id, role);
+        }
+
+        Ok(())
+    }
+
+    // nobody but the monitor's owner can lower the role required for an action
+    pub fn require(
+        &mut self,
+        owner: Option<UserId>,
+        caller: UserId,
+        action: Action,
+        role: Role
+    ) -> Result<(), String> {
+        if owner != Some(caller) && role < self.required(action) {
+            return Err(format!(
+                "Only the monitor's owner can lower the role required to {} (currently {})", 
+                action, self.required(action)
+            ));
+        }
+
+        self.required.insert(action, role);
+
+        Ok(())
+    }
+}
+
+impl Storable for ChatPermissions {
+    fn to_bytes(
+        &self
+    ) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(
+        bytes: Cow<[u8]>
+    ) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+    use super::*;
+
+    fn user(
+        id: u8
+    ) -> UserId {
+        Principal::from_slice(&[id])
+    }
+
+    #[test]
+    fn authorizes_by_role() {
+        let (owner, admin, member) = (user(1), user(2), user(3));
+        let mut permissions = ChatPermissions::default();
+        permissions.roles.insert(admin, Role::Admin);
+
+        assert!(permissions.authorize(Some(owner), owner, Action::Deploy).is_ok());
+        assert!(permissions.authorize(Some(owner), admin, Action::EditJobs).is_ok());
+        // admin is required if not set
+        assert!(permissions.authorize(Some(owner), member, Action::StartStopJobs).is_err());
+
+        permissions.required.insert(Action::StartStopJobs, Role::Member);
+        assert!(permissions.authorize(Some(owner), member, Action::StartStopJobs).is_ok());
+        assert!(permissions.authorize(Some(owner), member, Action::EditJobs).is_err());
+    }
+
+    #[test]
+    fn lets_chat_admins_bootstrap() {
+        let admin = user(2);
+        let mut permissions = ChatPermissions::default();
+
+        assert!(permissions.authorize(None, admin, Action::Deploy).is_ok());
+        assert!(permissions.authorize(None, admin, Action::Config).is_ok());
+        assert!(permissions.authorize(None, admin, Action::EditJobs).is_err());
+
+        // once a monitor is deployed or roles granted, only the roles count
+        assert!(permissions.authorize(Some(user(1)), admin, Action::Config).is_err());
+        permissions.roles.insert(user(3), Role::Moderator);
+        assert!(permissions.authorize(None, admin, Action::Deploy).is_err());
+    }
+
+    #[test]
+    fn caps_grants_at_the_callers_role() {
+        let (owner, admin, moderator, member) = (user(1), user(2), user(3), user(4));
+        let mut permissions = ChatPermissions::default();
+        permissions.roles.insert(admin, Role::Admin);
+        permissions.roles.insert(moderator, Role::Moderator);
+
+        // the owner isn't restricted
+        assert!(permissions.grant(Some(owner), owner, member, Role::Admin).is_ok());
+        assert_eq!(permissions.role_of(&member), Role::Admin);
+
+        // nor can anyone else grant, or take away, a role higher than their own
+        assert!(permissions.grant(Some(owner), moderator, user(5), Role::Admin).is_err());
+        assert!(permissions.grant(Some(owner), moderator, admin, Role::Member).is_err());
+        assert_eq!(permissions.role_of(&admin), Role::Admin);
+        assert!(permissions.grant(Some(owner), moderator, user(5), Role::Moderator).is_ok());
+
+        // granting the member role removes the user's
+        assert!(permissions.grant(Some(owner), admin, user(5), Role::Member).is_ok());
+        assert!(!permissions.roles.contains_key(&user(5)));
+
+        // the chat's admin granting the first roles counts as admin
+        let mut permissions = ChatPermissions::default();
+        assert!(permissions.grant(None, admin, moderator, Role::Admin).is_ok());
+        assert!(permissions.grant(None, user(5), member, Role::Moderator).is_err());
+    }
+
+    #[test]
+    fn only_lets_the_owner_lower_requirements() {
+        let (owner, admin) = (user(1), user(2));
+        let mut permissions = ChatPermissions::default();
+        permissions.roles.insert(admin, Role::Admin);
+
+        assert!(permissions.require(Some(owner), admin, Action::Config, Role::Member).is_err());
+        assert!(permissions.require(None, admin, Action::Deploy, Role::Member).is_err());
+        assert_eq!(permissions.required(Action::Config), Role::Admin);
+
+        assert!(permissions.require(Some(owner), owner, Action::Config, Role::Moderator).is_ok());
+        assert_eq!(permissions.required(Action::Config), Role::Moderator);
+
+        // raising it is fine
+        assert!(permissions.require(Some(owner), admin, Action::Config, Role::Admin).is_ok());
+        assert_eq!(permissions.required(Action::Config), Role::Admin);
+    }
+}
@@ -0,0 +1,304 @@
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_EVENTS_PER_MINUTE: u32 = 20;
+pub const DEFAULT_MAX_QUEUE: u32 = 100;
+const MAX_POST_ATTEMPTS: u32 = 5;
+const FAILURE_COOLDOWN: u64 = 10 * 60 * 1_000_000_000; // 10 minutes
+
+#[derive(Clone, Debug, Serialize, Deserialize, CandidType)]
+pub struct RateLimit {
+    // 0 disables the limit
+    pub per_minute: u32,
+    // max events posted at once
+    pub burst: u32,
+    // max events waiting to be posted, before they are suppressed
+    pub max_queue: u32,
+}
+
+impl Default for RateLimit {
+    fn default(
+    ) -> Self {
+        Self {
+            per_minute: DEFAULT_EVENTS_PER_MINUTE,
+            burst: DEFAULT_EVENTS_PER_MINUTE,
+            max_queue: DEFAULT_MAX_QUEUE,
+        }
+    }
+}
+
+// a token bucket per chat, where each event posted takes a token
+#[derive(Clone, Default, Serialize, Deserialize, CandidType)]
+pub struct ChatThrottle {
+    pub limit: Option<RateLimit>,
+    pub tokens: f64,
+    pub updated_at: u64,
+    pub queue: Vec<String>,
+    pub suppressed: u64,
+    // failed attempts to post the message at the front of the queue
+    pub attempts: Option<u32>,
+    // when and why messages were last dropped after too many attempts
+    pub failed: Option<(u64, String)>,
+}
+
+impl ChatThrottle {
+    pub fn new(
+        now: u64
+    ) -> Self {
+        let limit = RateLimit::default();
+        Self {
+            tokens: limit.burst as f64,
+            updated_at: now,
+            ..Default::default()
+        }
+    }
+
+    pub fn limit(
+        &self
+    ) -> RateLimit {
+        self.limit.clone().unwrap_or_default()
+    }
+
+    pub fn has_backlog(
+        &self
+    ) -> bool {
+        !self.queue.is_empty() || self.suppressed > 0
+    }
+
+    // queues the messages, suppressing the whole backlog if it grows too much
+    pub fn push(
+        &mut self,
+        messages: Vec<String>
+    ) {
+        self.queue.extend(messages);
+
+        let limit = self.limit();
+        if limit.per_minute > 0 && self.queue.len() > limit.max_queue as usize {
+            self.suppressed += self.queue.len() as u64;
+            self.queue.clear();
+        }
+    }
+
+    // removes the messages that can be posted now
+    pub fn take(
+        &mut self,
+        now: u64
+    ) -> Vec<String> {
+        let limit = self.limit();
+        if limit.per_minute == 0 {
+            return self.drain_all();
+        }
+
+        let elapsed = now.saturating_sub(self.updated_at) as f64 / 60_000_000_000.0;
+        self.tokens = (self.tokens + elapsed * limit.per_minute as f64).min(limit.burst.max(1) as f64);
+        self.updated_at = now;
+
+        let mut messages = vec![];
+        if self.suppressed > 0 && self.tokens >= 1.0 {
+            messages.push(format!(
+                "⚠️ {} events suppressed, as more than {} were waiting to be posted. Use `/eventmon config rate-limit` to change the limits",
+                self.suppressed, limit.max_queue
+            ));
+            self.suppressed = 0;
+            self.tokens -= 1.0;
+        }
+
+        let count = (self.tokens.floor() as usize).min(self.queue.len());
+        messages.extend(self.queue.drain(..count));
+        self.tokens -= count as f64;
+
+        messages
+    }
+
+    // puts back messages that couldn't be posted, unless the first one failed too many times.
+    // returns if they were dropped
+    pub fn requeue(
+        &mut self,
+        messages: Vec<String>,
+        partial: bool,
+        err: String,
+        now: u64
+    ) -> bool {
+        // posting some of them means the first one left was only tried once
+        let attempts = if partial { 1 } else { self.attempts.unwrap_or(0) + 1 };
+
+        if attempts >= MAX_POST_ATTEMPTS {
+            self.attempts = None;
+            self.failed = Some((now, err));
+            true
+        }
+        else {
+            self.attempts = Some(attempts);
+            self.queue.splice(0..0, messages);
+            false
+        }
+    }
+
+    pub fn posted(
+        &mut self
+    ) {
+        self.attempts = None;
+        self.failed = None;
+    }
+
+    // the reason new messages are refused, while posting to the chat keeps failing
+    pub fn failing(
+        &self,
+        now: u64
+    ) -> Option<&str> {
+        match &self.failed {
+            Some((at, err)) if now < at + FAILURE_COOLDOWN => Some(err.as_str()),
+            _ => None,
+        }
+    }
+
+    // nanoseconds until the next token is available
+    pub fn next_token_in(
+        &self
+    ) -> u64 {
+        let limit = self.limit();
+        if limit.per_minute == 0 || self.tokens >= 1.0 {
+            0
+        }
+        else {
+            ((1.0 - self.tokens) * 60_000_000_000.0 / limit.per_minute as f64) as u64
+        }
+    }
+
+    fn drain_all(
+        &mut self
+    ) -> Vec<String> {
+        let mut messages = vec![];
+        if self.suppressed > 0 {
+            messages.push(format!("⚠️ {} events suppressed", self.suppressed));
+            self.suppressed = 0;
+        }
+        messages.append(&mut self.queue);
+        messages
+    }
+}
+
+impl Storable for ChatThrottle {
+    fn to_bytes(
+        &self
+    ) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(
+        bytes: Cow<[u8]>
+    ) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000_000_000;
+
+    fn messages(
+        count: usize
+    ) -> Vec<String> {
+        (0..count).map(|i| i.to_string()).collect()
+    }
+
+    fn throttle(
+        per_minute: u32,
+        burst: u32,
+        max_queue: u32
+    ) -> ChatThrottle {
+        let mut throttle = ChatThrottle::new(0);
+        throttle.limit = Some(RateLimit { per_minute, burst, max_queue });
+        throttle.tokens = burst as f64;
+        throttle
+    }
+
+    #[test]
+    fn takes_the_tokens_available() {
+        let mut throttle = throttle(2, 3, 10);
+        throttle.push(messages(5));
+
+        assert_eq!(throttle.take(0), messages(3));
+        assert!(throttle.take(0).is_empty());
+        assert_eq!(throttle.next_token_in(), MINUTE / 2);
+
+        // a token every 30 seconds
+        assert_eq!(throttle.take(MINUTE / 2), vec!["3".to_string()]);
+        assert_eq!(throttle.take(10 * MINUTE), vec!["4".to_string()]);
+
+        // up to the burst
+        throttle.push(messages(5));
+        assert_eq!(throttle.take(10 * MINUTE).len(), 2);
+        assert_eq!(throttle.take(20 * MINUTE).len(), 3);
+        assert!(!throttle.has_backlog());
+    }
+
+    #[test]
+    fn suppresses_a_long_backlog() {
+        let mut throttle = throttle(1, 1, 3);
+        throttle.push(messages(3));
+        assert_eq!(throttle.queue.len(), 3);
+
+        throttle.push(messages(1));
+        assert!(throttle.queue.is_empty());
+        assert_eq!(throttle.suppressed, 4);
+
+        let taken = throttle.take(0);
+        assert_eq!(taken.len(), 1);
+        assert!(taken[0].contains("4 events suppressed"));
+        assert!(!throttle.has_backlog());
+    }
+
+    #[test]
+    fn posts_everything_without_a_limit() {
+        let mut throttle = throttle(0, 0, 3);
+        throttle.push(messages(10));
+
+        assert_eq!(throttle.take(0), messages(10));
+        assert_eq!(throttle.next_token_in(), 0);
+    }
+
+    #[test]
+    fn drops_messages_after_too_many_attempts() {
+        let mut throttle = throttle(0, 0, 10);
+        throttle.push(messages(2));
+
+        for _ in 1..MAX_POST_ATTEMPTS {
+            let taken = throttle.take(0);
+            assert!(!throttle.requeue(taken, false, "down".to_string(), 0));
+        }
+        assert_eq!(throttle.queue, messages(2));
+        assert!(throttle.failing(0).is_none());
+
+        let taken = throttle.take(0);
+        assert!(throttle.requeue(taken, false, "down".to_string(), 0));
+        assert!(throttle.queue.is_empty());
+
+        // new messages are refused for a while
+        assert_eq!(throttle.failing(FAILURE_COOLDOWN - 1), Some("down"));
+        assert!(throttle.failing(FAILURE_COOLDOWN).is_none());
+
+        throttle.posted();
+        assert!(throttle.failing(0).is_none());
+    }
+
+    #[test]
+    fn counts_attempts_from_the_first_message_left() {
+        let mut throttle = throttle(0, 0, 10);
+        throttle.push(messages(2));
+
+        // posting some of them starts counting again
+        for _ in 0..2 * MAX_POST_ATTEMPTS {
+            let taken = throttle.take(0);
+            assert!(!throttle.requeue(taken, true, "down".to_string(), 0));
+        }
+        assert_eq!(throttle.queue, messages(2));
+        assert_eq!(throttle.attempts, Some(1));
+    }
+}
@@ -0,0 +1,115 @@
+use std::{borrow::Cow, fmt::Display};
+use candid::{CandidType, Decode, Encode, Principal};
+use clap::ValueEnum;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+use crate::utils::decimal::parse_decimal;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, CandidType, ValueEnum)]
+#[value(rename_all = "UPPER")]
+pub enum Token {
+    Icp,
+    CkBtc,
+    Chat,
+    CkUsdc,
+}
+
+pub const TOKENS: [Token; 4] = [Token::Icp, Token::CkBtc, Token::Chat, Token::CkUsdc];
+
+impl Token {
+    pub fn ledger_canister_id(
+        &self
+    ) -> Principal {
+        Principal::from_text(match self {
+            Token::Icp => "ryjl3-tyaaa-aaaaa-aaaba-cai",
+            Token::CkBtc => "mxzaz-hqaaa-aaaar-qaada-cai",
+            Token::Chat => "2ouva-viaaa-aaaaq-aaamq-cai",
+            Token::CkUsdc => "xevnm-gaaaa-aaaar-qafnq-cai",
+        }).unwrap()
+    }
+
+    pub fn decimals(
+        &self
+    ) -> u32 {
+        match self {
+            Token::CkUsdc => 6,
+            _ => 8,
+        }
+    }
+
+    // the units in one token
+    pub fn unit(
+        &self
+    ) -> u128 {
+        10u128.pow(self.decimals())
+    }
+
+    // parses a decimal amount (ie: 1.25) to units, without rounding it
+    pub fn parse_units(
+        &self,
+        amount: &str
+    ) -> Result<u128, String> {
+        parse_decimal(amount, self.decimals())
+    }
+
+    pub fn from_units(
+        &self,
+        units: u128
+    ) -> f64 {
+        units as f64 / 10f64.powi(self.decimals() as i32)
+    }
+}
+
+impl Display for Token {
+    fn fmt(
+        &self, 
+        fmt: &mut std::fmt::Formatter<'_>
+    ) -> std::fmt::Result {
+        fmt.write_str(match self {
+            Token::Icp => "ICP",
+            Token::CkBtc => "ckBTC",
+            Token::Chat => "CHAT",
+            Token::CkUsdc => "ckUSDC",
+        })
+    }
+}
+
+impl Storable for Token {
+    fn to_bytes(
+        &self
+    ) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(
+        bytes: Cow<[u8]>
+    ) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// an ICPSwap pool converting the token to ICP
+#[derive(Clone, Debug, Serialize, Deserialize, CandidType)]
+pub struct SwapRoute {
+    pub pool_canister_id: Principal,
+    // true if the token is the pool's token0 and ICP its token1
+    pub zero_for_one: bool,
+}
+
+impl Storable for SwapRoute {
+    fn to_bytes(
+        &self
+    ) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(
+        bytes: Cow<[u8]>
+    ) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
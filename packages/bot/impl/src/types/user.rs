@@ -1,24 +1,134 @@
 use std::borrow::Cow;
 use candid::{CandidType, Decode, Encode, Principal};
-use ic_ledger_types::AccountIdentifier;
+use ic_ledger_types::{AccountIdentifier, Subaccount, DEFAULT_FEE};
 use ic_stable_structures::{storable::Bound, Storable};
 use serde::Deserialize;
+use super::token::Token;
 
 pub type UserId = Principal;
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
 pub enum UserTransaction {
+    IcpDeposit {
+        amount: u64,
+        from: AccountIdentifier,
+        block_num: u64,
+        timestamp: u32,
+    },
     IcpWithdraw {
         amount: u64,
         to: AccountIdentifier,
         block_num: u64,
         timestamp: u32,
     },
+    MonitorTopUp {
+        canister_id: Principal,
+        cycles: u128,
+        amount: u64,
+        timestamp: u32,
+    },
+    DeploymentFee {
+        amount: u64,
+        block_num: u64,
+        timestamp: u32,
+    },
+    DeploymentRefund {
+        amount: u64,
+        block_num: u64,
+        timestamp: u32,
+    },
+    TokenWithdraw {
+        token: Token,
+        amount: u128,
+        to: String,
+        block_num: u64,
+        timestamp: u32,
+    },
+    // ICP received for the token amount
+    TokenSwap {
+        token: Token,
+        token_amount: u128,
+        amount: u64,
+        timestamp: u32,
+    },
+    // ICP worth of the cycles withdrawn from a deleted monitor
+    MonitorRefund {
+        canister_id: Principal,
+        cycles: u128,
+        amount: u64,
+        block_num: u64,
+        timestamp: u32,
+    },
+}
+
+impl UserTransaction {
+    pub fn timestamp(
+        &self
+    ) -> u32 {
+        match self {
+            UserTransaction::IcpDeposit { timestamp, .. } |
+            UserTransaction::IcpWithdraw { timestamp, .. } |
+            UserTransaction::MonitorTopUp { timestamp, .. } |
+            UserTransaction::DeploymentFee { timestamp, .. } |
+            UserTransaction::DeploymentRefund { timestamp, .. } |
+            UserTransaction::TokenWithdraw { timestamp, .. } |
+            UserTransaction::TokenSwap { timestamp, .. } |
+            UserTransaction::MonitorRefund { timestamp, .. } => *timestamp,
+        }
+    }
+
+    // change to the user's ICP balance, in e8s, including the ledger fees paid
+    pub fn balance_change(
+        &self
+    ) -> i128 {
+        match self {
+            UserTransaction::IcpDeposit { amount, .. } |
+            UserTransaction::DeploymentRefund { amount, .. } |
+            UserTransaction::TokenSwap { amount, .. } |
+            UserTransaction::MonitorRefund { amount, .. } => {
+                *amount as i128
+            },
+            UserTransaction::IcpWithdraw { amount, .. } |
+            UserTransaction::MonitorTopUp { amount, .. } |
+            UserTransaction::DeploymentFee { amount, .. } => {
+                -((*amount + DEFAULT_FEE.e8s()) as i128)
+            },
+            UserTransaction::TokenWithdraw { .. } => {
+                0
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub enum CreditReason {
+    MonitorDeleted {
+        canister_id: Principal,
+        cycles: u128,
+    },
+    TokenSwap {
+        token: Token,
+        token_amount: u128,
+    },
+}
+
+// ICP owed to the user, kept until its transfer to the user's wallet succeeds
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct PendingCredit {
+    // the bot's subaccount holding the ICP, its default one if None
+    pub from: Option<Subaccount>,
+    pub amount: u64,
+    pub reason: CreditReason,
 }
 
 #[derive(Default, CandidType, Deserialize)]
 pub struct User {
     pub txs: Vec<UserTransaction>,
+    // id, at the ICP index, of the last transaction scanned for deposits
+    pub last_scanned_tx: Option<u64>,
+    pub pending_credits: Option<Vec<PendingCredit>>,
+    // (timestamp, text) of the alerts not read yet, oldest first
+    pub notices: Option<Vec<(u32, String)>>,
 }
 
 impl Storable for User {
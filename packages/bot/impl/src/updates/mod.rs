@@ -1,4 +1,5 @@
 pub mod notify_events;
 pub mod update_monitors;
 pub mod start_monitors;
-pub mod stop_monitors;
\ No newline at end of file
+pub mod stop_monitors;
+pub mod set_swap_route;
\ No newline at end of file
@@ -1,14 +1,18 @@
-use bot_api::{updates::notify_events::{NotifiyEventsArgs, NotifiyEventsResponse}, NOTIFY_EVENT_COST};
+use bot_api::{
+    updates::notify_events::{JobEvent, NotifiyEventsArgs, NotifiyEventsResponse}, 
+    NOTIFY_EVENT_COST
+};
 use ic_cdk::api::call::{msg_cycles_accept128, msg_cycles_available};
-use oc_bots_sdk::{
-    oc_api::actions::{send_message, ActionArgsBuilder}, 
-    types::{
-        ActionScope, BotApiKeyContext, BotPermissions, 
-        Chat, MessageContentInitial, TextContent
-    }
+use crate::{
+    guards::*, 
+    services::throttle::ThrottleService, 
+    storage::monitor::MonitorStorage, 
+    types::monitor::Monitor, 
+    utils::chat::{destination_to_chat, has_text_api_key}
 };
-use oc_bots_sdk_canister::OPENCHAT_CLIENT_FACTORY;
-use crate::{guards::*, state, storage::monitor::MonitorStorage};
+
+// how many (job id, event index) pairs are kept per monitor to detect redeliveries
+const MAX_DELIVERED_EVENTS: usize = 1000;
 
 #[ic_cdk::update(guard = "monitor_canister_only")]
 pub async fn notify_events(
@@ -25,51 +29,96 @@ pub async fn notify_events(
 
     msg_cycles_accept128(NOTIFY_EVENT_COST as _);
 
-    let mon = MonitorStorage::load_by_canister_id(&ic_cdk::caller()).unwrap();
-
-    state::read(|s| {
-        if let Some(api_key) = s.api_key_registry().get_key_with_required_permissions(
-            &ActionScope::Chat(mon.chat),
-            &BotPermissions::text_only(),
-        ).cloned() {
-            ic_cdk::spawn(
-                send_messages(
-                    api_key.to_context(), 
-                    mon.chat, 
-                    args.messages
-                )
-            )
+    let Some(mon) = MonitorStorage::load_by_canister_id(&ic_cdk::caller()) else {
+        let err = format!("Unknown monitor {}", ic_cdk::caller().to_text());
+        ic_cdk::println!("error: {}", err);
+        return Err(err);
+    };
+
+    let chat = match &args.destination {
+        Some(destination) => destination_to_chat(destination),
+        None => mon.chat,
+    };
+
+    if !has_text_api_key(chat) {
+        let err = match &args.destination {
+            Some(destination) => format!("No API key with permission to send text messages to the {}", destination),
+            None => "No API key with permission to send text messages to the chat".to_string(),
+        };
+        ic_cdk::println!("error: {}", err);
+        return Err(err);
+    }
+
+    if let Err(err) = ThrottleService::check(chat) {
+        ic_cdk::println!("error: {}", err);
+        return Err(err);
+    }
+
+    // once queued, the bot is in charge of the messages: they are posted as the chat's 
+    // rate limit allows and retried a few times. While posting to the chat keeps failing 
+    // they are refused, so the monitor retries them later or moves them to its dead letters
+    let messages = match args.events {
+        // version 2+
+        Some(events) => {
+            let events = events.into_iter()
+                .filter(|e| !was_delivered(&mon, e))
+                .collect::<Vec<_>>();
+
+            let mut messages = events.iter()
+                .map(|e| e.text.clone())
+                .collect::<Vec<_>>();
+            
+            // a digest is posted as a single message, between its header and footer
+            if !messages.is_empty() {
+                if let Some(header) = args.header {
+                    messages[0] = format!("{}  \n{}", header, messages[0]);
+                }
+                if let Some(footer) = args.footer {
+                    let last = messages.len() - 1;
+                    messages[last] = format!("{}  \n{}", messages[last], footer);
+                }
+            }
+
+            set_delivered(&mon, &events);
+            messages
+        },
+        // version 1, from monitors not upgraded yet
+        None => {
+            args.messages
         }
-    });
+    };
+
+    ThrottleService::post(chat, messages).await;
 
     Ok(())
 }
 
-async fn send_messages(
-    ctx: BotApiKeyContext,
-    chat: Chat,
-    messages: Vec<String>
+fn was_delivered(
+    mon: &Monitor,
+    event: &JobEvent
+) -> bool {
+    mon.delivered.as_ref()
+        .map(|delivered| delivered.contains(&(event.job_id, event.index)))
+        .unwrap_or(false)
+}
+
+fn set_delivered(
+    mon: &Monitor,
+    events: &[JobEvent]
 ) {
-    if messages.len() > 0 {
-        let text = messages
-            .join("  \n---  \n")
-            .replace("\\n", "\n");
-        
-        match OPENCHAT_CLIENT_FACTORY
-            .build(ctx)
-            .send_message(MessageContentInitial::Text(TextContent { text }))
-            .with_channel_id(chat.channel_id())
-            .with_block_level_markdown(true)
-            .execute_async()
-            .await
-        {
-            Ok(send_message::Response::Success(_)) => (),
-            Err((code, message)) => {
-                ic_cdk::println!("error: Failed to send events: code({}): message({})", code, message);
-            }
-            other => {
-                ic_cdk::println!("error: Failed to send events {:?}", other);
-            }
+    if events.is_empty() {
+        return;
+    }
+
+    // reload, as the monitor may have changed in the meantime
+    let mon_id = mon.chat.into();
+    if let Some(mut mon) = MonitorStorage::load(&mon_id) {
+        let delivered = mon.delivered.get_or_insert_with(Vec::new);
+        delivered.extend(events.iter().map(|e| (e.job_id, e.index)));
+        if delivered.len() > MAX_DELIVERED_EVENTS {
+            delivered.drain(..delivered.len() - MAX_DELIVERED_EVENTS);
         }
+
+        MonitorStorage::save(mon_id, mon);
     }
-}
\ No newline at end of file
+}
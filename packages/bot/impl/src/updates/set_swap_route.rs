@@ -0,0 +1,22 @@
+use bot_api::updates::set_swap_route::{SetSwapRouteArgs, SetSwapRouteResponse};
+use clap::ValueEnum;
+use crate::{
+    guards::*, 
+    services::swap::SwapService, 
+    types::token::{SwapRoute, Token}
+};
+
+#[ic_cdk::update(guard = "admin_only")]
+fn set_swap_route(
+    args: SetSwapRouteArgs
+) -> SetSwapRouteResponse {
+    let token = Token::from_str(&args.token, true)?;
+
+    SwapService::set_route(
+        token, 
+        args.pool_canister_id.map(|pool_canister_id| SwapRoute {
+            pool_canister_id,
+            zero_for_one: args.zero_for_one,
+        })
+    )
+}
@@ -0,0 +1,105 @@
+use monitor_api::types::job::JobDestination;
+use oc_bots_sdk::{
+    oc_api::actions::{send_message, ActionArgsBuilder}, 
+    types::{
+        ActionScope, BotApiKeyContext, BotPermissions, 
+        Chat, MessageContentInitial, TextContent
+    }
+};
+use oc_bots_sdk_canister::OPENCHAT_CLIENT_FACTORY;
+use crate::{state, utils::chunks::split_messages};
+
+// OpenChat's text message limit, minus room for the part number
+const MAX_TEXT_LENGTH: usize = 10_000 - 32;
+const MAX_SEND_ATTEMPTS: u32 = 3;
+
+pub fn destination_to_chat(
+    destination: &JobDestination
+) -> Chat {
+    match destination {
+        JobDestination::Group(canister_id) => Chat::Group(*canister_id),
+        JobDestination::Channel(canister_id, channel_id) => Chat::Channel(*canister_id, *channel_id),
+    }
+}
+
+pub fn has_text_api_key(
+    chat: Chat
+) -> bool {
+    get_text_api_key(chat).is_some()
+}
+
+pub fn get_text_api_key(
+    chat: Chat
+) -> Option<BotApiKeyContext> {
+    state::read(|s| {
+        s.api_key_registry().get_key_with_required_permissions(
+            &ActionScope::Chat(chat),
+            &BotPermissions::text_only(),
+        ).cloned().map(|api_key| api_key.to_context())
+    })
+}
+
+// long batches are split in parts, posted in order
+pub async fn send_messages<F>(
+    ctx: BotApiKeyContext,
+    chat: Chat,
+    messages: Vec<String>,
+    mut on_posted: F
+) -> Result<(), String> 
+    where F: FnMut(usize) {
+    let messages = messages.into_iter()
+        .map(|m| m.replace("\\n", "\n"))
+        .collect::<Vec<_>>();
+
+    let chunks = split_messages(&messages, MAX_TEXT_LENGTH);
+    let count = chunks.len();
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        let text = if count > 1 {
+            format!("{}  \n\n_{}/{}_", chunk.text, i + 1, count)
+        }
+        else {
+            chunk.text
+        };
+
+        send_text(ctx.clone(), chat, text).await?;
+        on_posted(chunk.completed);
+    }
+
+    Ok(())
+}
+
+async fn send_text(
+    ctx: BotApiKeyContext,
+    chat: Chat,
+    text: String
+) -> Result<(), String> {
+    let mut attempt = 1;
+    loop {
+        let err = match OPENCHAT_CLIENT_FACTORY
+            .build(ctx.clone())
+            .send_message(MessageContentInitial::Text(TextContent { text: text.clone() }))
+            .with_channel_id(chat.channel_id())
+            .with_block_level_markdown(true)
+            .execute_async()
+            .await
+        {
+            Ok(send_message::Response::Success(_)) => {
+                return Ok(());
+            },
+            Err((code, message)) => {
+                format!("Failed to send events: code({}): message({})", code, message)
+            },
+            other => {
+                format!("Failed to send events: {:?}", other)
+            }
+        };
+
+        ic_cdk::println!("error: {} (attempt {}/{})", err, attempt, MAX_SEND_ATTEMPTS);
+        if attempt >= MAX_SEND_ATTEMPTS {
+            return Err(err);
+        }
+
+        attempt += 1;
+    }
+}
+
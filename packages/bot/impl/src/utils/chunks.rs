@@ -0,0 +1,248 @@
+use std::mem::take;
+
+const SEPARATOR: &str = "  \n---  \n";
+const FENCE: &str = "```";
+
+pub struct Chunk {
+    pub text: String,
+    // number of messages fully posted once this chunk is posted
+    pub completed: usize,
+}
+
+// packs the messages into texts of up to max_len bytes, splitting between messages first,
+// then between lines, keeping code blocks whole unless a single block doesn't fit
+pub fn split_messages(
+    messages: &[String],
+    max_len: usize
+) -> Vec<Chunk> {
+    let mut chunks = vec![];
+    let mut text = String::new();
+
+    for (i, message) in messages.iter().enumerate() {
+        let sep = if text.is_empty() { "" } else { SEPARATOR };
+        if text.len() + sep.len() + message.len() <= max_len {
+            text.push_str(sep);
+            text.push_str(message);
+            continue;
+        }
+
+        if !text.is_empty() {
+            chunks.push(Chunk {
+                text: take(&mut text),
+                completed: i,
+            });
+        }
+
+        if message.len() <= max_len {
+            text = message.clone();
+        }
+        else {
+            let mut parts = split_message(message, max_len);
+            text = parts.pop().unwrap_or_default();
+            chunks.extend(parts.into_iter().map(|text| Chunk {
+                text,
+                completed: i,
+            }));
+        }
+    }
+
+    if !text.is_empty() {
+        chunks.push(Chunk {
+            text,
+            completed: messages.len(),
+        });
+    }
+
+    chunks
+}
+
+fn split_message(
+    message: &str,
+    max_len: usize
+) -> Vec<String> {
+    let mut parts = vec![];
+    let mut part: Option<String> = None;
+
+    for block in blocks(message) {
+        if let Some(text) = &mut part {
+            if text.len() + 1 + block.len() <= max_len {
+                text.push('\n');
+                text.push_str(&block);
+                continue;
+            }
+
+            parts.extend(part.take());
+        }
+
+        if block.len() <= max_len {
+            part = Some(block);
+        }
+        else {
+            let mut pieces = split_block(&block, max_len);
+            part = pieces.pop();
+            parts.extend(pieces);
+        }
+    }
+
+    parts.extend(part);
+    parts
+}
+
+// the lines of a message, with each code block as a single item
+fn blocks(
+    message: &str
+) -> Vec<String> {
+    let mut blocks = vec![];
+    let mut code: Option<String> = None;
+
+    for line in message.split('\n') {
+        let fence = line.trim_start().starts_with(FENCE);
+        match &mut code {
+            Some(block) => {
+                block.push('\n');
+                block.push_str(line);
+                if fence {
+                    blocks.extend(code.take());
+                }
+            },
+            // a fence closed on the same line, ie: ```code```, is inline
+            None if fence && !line.trim_start()[FENCE.len()..].contains(FENCE) => {
+                code = Some(line.to_string());
+            },
+            None => {
+                blocks.push(line.to_string());
+            }
+        }
+    }
+
+    // unclosed block
+    blocks.extend(code);
+    blocks
+}
+
+// splits a line or code block that doesn't fit, closing and reopening the code block between parts
+fn split_block(
+    block: &str,
+    max_len: usize
+) -> Vec<String> {
+    if !block.trim_start().starts_with(FENCE) {
+        return split_at_len(block, max_len);
+    }
+
+    let mut lines = block.split('\n').collect::<Vec<_>>();
+    let open = lines.remove(0);
+    if lines.last().map(|line| line.trim_start().starts_with(FENCE)).unwrap_or(false) {
+        lines.pop();
+    }
+
+    let max_len = max_len.saturating_sub(open.len() + FENCE.len() + 2).max(1);
+    let mut pieces = vec![];
+    let mut piece: Option<String> = None;
+
+    for line in lines.iter().flat_map(|line| split_at_len(line, max_len)) {
+        if let Some(text) = &mut piece {
+            if text.len() + 1 + line.len() <= max_len {
+                text.push('\n');
+                text.push_str(&line);
+                continue;
+            }
+
+            pieces.extend(piece.take());
+        }
+
+        piece = Some(line);
+    }
+
+    pieces.extend(piece);
+    pieces.into_iter()
+        .map(|piece| format!("{}\n{}\n{}", open, piece, FENCE))
+        .collect()
+}
+
+fn split_at_len(
+    text: &str,
+    max_len: usize
+) -> Vec<String> {
+    let mut parts = vec![];
+    let mut part = String::new();
+
+    for c in text.chars() {
+        if !part.is_empty() && part.len() + c.len_utf8() > max_len {
+            parts.push(take(&mut part));
+        }
+        part.push(c);
+    }
+
+    parts.push(part);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(
+        chunks: &[Chunk]
+    ) -> Vec<&str> {
+        chunks.iter()
+            .map(|chunk| chunk.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn packs_messages() {
+        let messages = vec!["aaaa".to_string(), "bbbb".to_string(), "cccc".to_string()];
+        let len = 4 + SEPARATOR.len() + 4;
+
+        // exactly at the limit
+        let chunks = split_messages(&messages, len);
+        assert_eq!(texts(&chunks), [format!("aaaa{}bbbb", SEPARATOR).as_str(), "cccc"]);
+        assert_eq!(chunks.iter().map(|c| c.completed).collect::<Vec<_>>(), [2, 3]);
+
+        // one byte short
+        let chunks = split_messages(&messages, len - 1);
+        assert_eq!(texts(&chunks), ["aaaa", "bbbb", "cccc"]);
+        assert_eq!(chunks.iter().map(|c| c.completed).collect::<Vec<_>>(), [1, 2, 3]);
+
+        assert!(split_messages(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn splits_between_lines() {
+        let messages = vec!["line 1\nline 2\nline 3".to_string()];
+
+        let chunks = split_messages(&messages, 13);
+        assert_eq!(texts(&chunks), ["line 1\nline 2", "line 3"]);
+        // only the last part completes the message
+        assert_eq!(chunks.iter().map(|c| c.completed).collect::<Vec<_>>(), [0, 1]);
+    }
+
+    #[test]
+    fn splits_long_lines() {
+        let chunks = split_messages(&["abcdefg".to_string()], 3);
+        assert_eq!(texts(&chunks), ["abc", "def", "g"]);
+
+        // never splits a multi-byte char
+        let chunks = split_messages(&["ééé".to_string()], 3);
+        assert_eq!(texts(&chunks), ["é", "é", "é"]);
+    }
+
+    #[test]
+    fn keeps_code_blocks_whole() {
+        let message = "intro\n```\ncode 1\ncode 2\n```\noutro".to_string();
+
+        let chunks = split_messages(&[message], 26);
+        assert_eq!(texts(&chunks), ["intro", "```\ncode 1\ncode 2\n```", "outro"]);
+    }
+
+    #[test]
+    fn reopens_split_code_blocks() {
+        let message = "```\ncode 1\ncode 2\ncode 3\n```".to_string();
+
+        let chunks = split_messages(&[message], 22);
+        assert_eq!(texts(&chunks), ["```\ncode 1\ncode 2\n```", "```\ncode 3\n```"]);
+        for chunk in &chunks {
+            assert!(chunk.text.len() <= 22);
+        }
+    }
+}
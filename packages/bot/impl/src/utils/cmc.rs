@@ -1,11 +1,146 @@
+use std::fmt::Display;
+use candid::{CandidType, Nat, Principal};
 use canfund::api::cmc::GetIcpXdrResult;
-use ic_ledger_types::MAINNET_CYCLES_MINTING_CANISTER_ID;
+use ic_ledger_types::{
+    AccountIdentifier, Memo, Subaccount, Timestamp, Tokens, TransferArgs, 
+    DEFAULT_FEE, MAINNET_CYCLES_MINTING_CANISTER_ID, MAINNET_LEDGER_CANISTER_ID
+};
+use serde::Deserialize;
+use super::nat::nat_to_u128;
 
 const CYCLES_PER_XDR: u128 = 1_000_000_000_000; // 1 trillion cycles per XDR
+const MEMO_TOP_UP_CANISTER: u64 = 0x50555054; // "TPUP"
+const NOTIFY_ATTEMPTS: usize = 3;
+
+#[derive(CandidType, Deserialize)]
+struct NotifyTopUpArg {
+    block_index: u64,
+    canister_id: Principal,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+enum NotifyError {
+    Refunded {
+        block_index: Option<u64>,
+        reason: String,
+    },
+    InvalidTransaction(String),
+    Other {
+        error_message: String,
+        error_code: u64,
+    },
+    Processing,
+    TransactionTooOld(u64),
+}
+
+type NotifyTopUpResult = Result<Nat, NotifyError>;
+
+#[derive(Debug)]
+pub enum TopUpError {
+    // nothing was transferred to the CMC
+    Transfer(String),
+    // the ICP is at the CMC: notifying it can be retried with the block index
+    Notify {
+        block_index: u64,
+        details: String,
+    },
+    // the CMC won't mint the cycles for the block, ie: it refunded the ICP or the block is too old
+    Rejected {
+        block_index: u64,
+        details: String,
+    },
+}
+
+impl Display for TopUpError {
+    fn fmt(
+        &self, 
+        fmt: &mut std::fmt::Formatter<'_>
+    ) -> std::fmt::Result {
+        match self {
+            TopUpError::Transfer(details) => {
+                write!(fmt, "transferring the ICP to the CMC: {}", details)
+            },
+            TopUpError::Notify { block_index, details } => {
+                write!(fmt, "notifying the CMC of the top-up at block {}: {}", block_index, details)
+            },
+            TopUpError::Rejected { block_index, details } => {
+                write!(fmt, "the CMC rejected the top-up at block {}: {}", block_index, details)
+            },
+        }
+    }
+}
 
 pub struct Cmc;
 
 impl Cmc {
+    // mints cycles for the canister with e8s (plus the ledger fee) from the bot's subaccount. Returns the cycles minted
+    pub async fn top_up(
+        from_subaccount: Subaccount,
+        canister_id: Principal,
+        e8s: u64
+    ) -> Result<u128, TopUpError> {
+        let block_index = ic_ledger_types::transfer(
+            MAINNET_LEDGER_CANISTER_ID, 
+            TransferArgs { 
+                from_subaccount: Some(from_subaccount), 
+                to: AccountIdentifier::new(
+                    &MAINNET_CYCLES_MINTING_CANISTER_ID, 
+                    &Subaccount::from(canister_id)
+                ), 
+                fee: DEFAULT_FEE, 
+                created_at_time: Some(Timestamp { timestamp_nanos: ic_cdk::api::time() }), 
+                memo: Memo(MEMO_TOP_UP_CANISTER), 
+                amount: Tokens::from_e8s(e8s),
+            }
+        ).await
+            .map_err(|e| TopUpError::Transfer(e.1))?
+            .map_err(|e| TopUpError::Transfer(e.to_string()))?;
+
+        Self::notify_top_up(canister_id, block_index).await
+    }
+
+    // the ICP is at the CMC: notifying can be retried by anyone with the block index
+    pub async fn notify_top_up(
+        canister_id: Principal,
+        block_index: u64
+    ) -> Result<u128, TopUpError> {
+        let mut last_err = String::new();
+        for _ in 0..NOTIFY_ATTEMPTS {
+            let res = ic_cdk::call::<(NotifyTopUpArg, ), (NotifyTopUpResult, )>(
+                MAINNET_CYCLES_MINTING_CANISTER_ID,
+                "notify_top_up",
+                (NotifyTopUpArg { block_index, canister_id }, )
+            ).await;
+
+            match res {
+                Ok((Ok(cycles), )) => {
+                    return Ok(nat_to_u128(cycles));
+                },
+                Ok((Err(NotifyError::Processing), )) => {
+                    last_err = "still processing".to_string();
+                },
+                Ok((Err(err @ (NotifyError::Refunded { .. } | NotifyError::InvalidTransaction(_) | NotifyError::TransactionTooOld(_))), )) => {
+                    return Err(TopUpError::Rejected { 
+                        block_index, 
+                        details: format!("{:?}", err) 
+                    });
+                },
+                Ok((Err(err), )) => {
+                    last_err = format!("{:?}", err);
+                    break;
+                },
+                Err(err) => {
+                    last_err = err.1;
+                }
+            }
+        }
+
+        Err(TopUpError::Notify { 
+            block_index, 
+            details: last_err 
+        })
+    }
+
     pub async fn cycles_to_icp(
         cycles: u128
     ) -> Result<u128, String> {
@@ -19,6 +154,19 @@ impl Cmc {
         )
     }
 
+    pub async fn icp_to_cycles(
+        e8s: u128
+    ) -> Result<u128, String> {
+        let price = Self::get_icp_xdr_rate().await?;
+
+        Ok(
+            Self::calculate_cycles_amount(
+                e8s, 
+                price
+            )
+        )
+    }
+
     async fn get_icp_xdr_rate(
     ) -> Result<u128, String> {
         let res = ic_cdk::call::<(), (GetIcpXdrResult,)>(
@@ -39,4 +187,12 @@ impl Cmc {
         let cycles_per_icp = xdr_permyriad_per_icp * CYCLES_PER_XDR / 10_000u128;
         cycles_amount * 100_000_000u128 / cycles_per_icp
     }
+
+    fn calculate_cycles_amount(
+        e8s: u128, 
+        xdr_permyriad_per_icp: u128
+    ) -> u128 {
+        let cycles_per_icp = xdr_permyriad_per_icp * CYCLES_PER_XDR / 10_000u128;
+        e8s * cycles_per_icp / 100_000_000u128
+    }
 }
\ No newline at end of file
@@ -0,0 +1,42 @@
+// parses a decimal amount (ie: 1.25) to units with the given decimals, without rounding it
+pub fn parse_decimal(
+    amount: &str,
+    decimals: u32
+) -> Result<u128, String> {
+    let (int, frac) = amount.split_once('.').unwrap_or((amount, ""));
+    if (int.is_empty() && frac.is_empty()) || 
+        !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid amount: {}", amount));
+    }
+
+    if frac.len() > decimals as usize {
+        return Err(format!("Invalid amount: {} has more than {} decimals", amount, decimals));
+    }
+
+    format!("{}{:0<width$}", int, frac, width = decimals as usize)
+        .parse::<u128>()
+        .map_err(|e| format!("Invalid amount {}: {}", amount, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_without_rounding() {
+        assert_eq!(parse_decimal("1.25", 8), Ok(125_000_000));
+        assert_eq!(parse_decimal("0.5", 12), Ok(500_000_000_000));
+        assert_eq!(parse_decimal(".1", 2), Ok(10));
+        assert_eq!(parse_decimal("3", 0), Ok(3));
+        assert_eq!(parse_decimal("0.00000001", 8), Ok(1));
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        assert!(parse_decimal("", 8).is_err());
+        assert!(parse_decimal(".", 8).is_err());
+        assert!(parse_decimal("-1", 8).is_err());
+        assert!(parse_decimal("1e3", 8).is_err());
+        assert!(parse_decimal("0.000000001", 8).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use candid::{CandidType, Principal};
+use ic_ledger_types::{AccountIdentifier, Tokens};
+use serde::Deserialize;
+use crate::consts::ICP_INDEX_CANISTER_ID;
+
+const MAX_RESULTS: u64 = 100;
+
+#[derive(CandidType, Deserialize)]
+struct GetAccountIdentifierTransactionsArgs {
+    account_identifier: String,
+    start: Option<u64>,
+    max_results: u64,
+}
+
+#[derive(CandidType, Deserialize)]
+struct GetAccountIdentifierTransactionsResponse {
+    balance: u64,
+    transactions: Vec<TransactionWithId>,
+    oldest_tx_id: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct GetAccountIdentifierTransactionsError {
+    message: String,
+}
+
+type GetAccountIdentifierTransactionsResult = Result<GetAccountIdentifierTransactionsResponse, GetAccountIdentifierTransactionsError>;
+
+#[derive(CandidType, Deserialize)]
+pub struct TransactionWithId {
+    pub id: u64,
+    pub transaction: Transaction,
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct Transaction {
+    pub operation: Operation,
+    pub timestamp: Option<TimeStamp>,
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct TimeStamp {
+    pub timestamp_nanos: u64,
+}
+
+#[derive(CandidType, Deserialize)]
+pub enum Operation {
+    Approve {
+        from: String,
+        spender: String,
+    },
+    Burn {
+        from: String,
+        amount: Tokens,
+    },
+    Mint {
+        to: String,
+        amount: Tokens,
+    },
+    Transfer {
+        from: String,
+        to: String,
+        amount: Tokens,
+        fee: Tokens,
+    },
+}
+
+pub struct IcpIndex;
+
+impl IcpIndex {
+    // the account's transactions newer than after_id (all if None), newest first, and its balance
+    pub async fn transactions_since(
+        account: AccountIdentifier,
+        after_id: Option<u64>
+    ) -> Result<(Vec<TransactionWithId>, u64), String> {
+        let index = Principal::from_text(ICP_INDEX_CANISTER_ID).unwrap();
+
+        let mut txs: Vec<TransactionWithId> = vec![];
+        let mut balance = None;
+        let mut start = None;
+
+        loop {
+            let res = ic_cdk::call::<(GetAccountIdentifierTransactionsArgs, ), (GetAccountIdentifierTransactionsResult, )>(
+                index,
+                "get_account_identifier_transactions",
+                (GetAccountIdentifierTransactionsArgs {
+                    account_identifier: account.to_hex(),
+                    start,
+                    max_results: MAX_RESULTS,
+                },)
+            ).await
+                .map_err(|e| e.1)?
+                .0
+                .map_err(|e| e.message)?;
+
+            balance.get_or_insert(res.balance);
+
+            let oldest_seen = txs.last().map(|tx| tx.id);
+            let mut done = res.transactions.len() < MAX_RESULTS as usize;
+
+            for tx in res.transactions {
+                // start may be inclusive
+                if oldest_seen.is_some_and(|id| tx.id >= id) {
+                    continue;
+                }
+
+                if after_id.is_some_and(|id| tx.id <= id) {
+                    done = true;
+                    break;
+                }
+
+                txs.push(tx);
+            }
+
+            let oldest = txs.last().map(|tx| tx.id);
+            if done || oldest == oldest_seen || oldest == res.oldest_tx_id {
+                break;
+            }
+
+            start = oldest;
+        }
+
+        Ok((txs, balance.unwrap_or_default()))
+    }
+}
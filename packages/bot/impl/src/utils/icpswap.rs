@@ -0,0 +1,196 @@
+use candid::{CandidType, Nat, Principal};
+use ic_ledger_types::Subaccount;
+use icrc_ledger_types::icrc1::account::Account;
+use serde::Deserialize;
+use crate::types::token::SwapRoute;
+use super::nat::nat_to_u128;
+
+#[derive(CandidType, Deserialize)]
+struct SwapArgs {
+    #[serde(rename = "amountIn")]
+    amount_in: String,
+    #[serde(rename = "zeroForOne")]
+    zero_for_one: bool,
+    #[serde(rename = "amountOutMinimum")]
+    amount_out_minimum: String,
+}
+
+#[derive(CandidType, Deserialize)]
+struct DepositArgs {
+    token: String,
+    amount: Nat,
+    fee: Nat,
+}
+
+#[derive(CandidType, Deserialize)]
+struct WithdrawArgs {
+    token: String,
+    amount: Nat,
+    fee: Nat,
+}
+
+#[derive(CandidType, Deserialize)]
+struct UnusedBalance {
+    balance0: Nat,
+    balance1: Nat,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+enum SwapError {
+    CommonError,
+    InternalError(String),
+    UnsupportedToken(String),
+    InsufficientFunds,
+}
+
+#[derive(CandidType, Deserialize)]
+enum UnusedBalanceResult {
+    #[serde(rename = "ok")]
+    Ok(UnusedBalance),
+    #[serde(rename = "err")]
+    Err(SwapError),
+}
+
+#[derive(CandidType, Deserialize)]
+enum SwapResult {
+    #[serde(rename = "ok")]
+    Ok(Nat),
+    #[serde(rename = "err")]
+    Err(SwapError),
+}
+
+impl SwapResult {
+    fn into_result(
+        self
+    ) -> Result<u128, String> {
+        match self {
+            SwapResult::Ok(amount) => {
+                Ok(nat_to_u128(amount))
+            },
+            SwapResult::Err(err) => {
+                Err(format!("{:?}", err))
+            }
+        }
+    }
+}
+
+// an ICPSwap (v3) pool
+pub struct IcpSwapPool;
+
+impl IcpSwapPool {
+    // how much the amount in would be swapped to now
+    pub async fn quote(
+        route: &SwapRoute,
+        amount_in: u128
+    ) -> Result<u128, String> {
+        let res = ic_cdk::call::<(SwapArgs, ), (SwapResult, )>(
+            route.pool_canister_id, 
+            "quote", 
+            (SwapArgs {
+                amount_in: amount_in.to_string(),
+                zero_for_one: route.zero_for_one,
+                amount_out_minimum: "0".to_string(),
+            }, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        res.0.into_result()
+    }
+
+    // the account where the caller must transfer the tokens to deposit
+    pub fn deposit_account(
+        route: &SwapRoute,
+        caller: Principal
+    ) -> Account {
+        Account {
+            owner: route.pool_canister_id,
+            subaccount: Some(Subaccount::from(caller).0),
+        }
+    }
+
+    // credits the tokens transferred to the caller's deposit account to the caller's unused balance.
+    // Returns the amount credited
+    pub async fn deposit(
+        route: &SwapRoute,
+        ledger: Principal,
+        amount: u128,
+        fee: u128
+    ) -> Result<u128, String> {
+        let res = ic_cdk::call::<(DepositArgs, ), (SwapResult, )>(
+            route.pool_canister_id, 
+            "deposit", 
+            (DepositArgs {
+                token: ledger.to_text(),
+                amount: amount.into(),
+                fee: fee.into(),
+            }, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        res.0.into_result()
+    }
+
+    // swaps the amount in from the caller's unused balance. Returns the amount out, added to it
+    pub async fn swap(
+        route: &SwapRoute,
+        amount_in: u128,
+        min_amount_out: u128
+    ) -> Result<u128, String> {
+        let res = ic_cdk::call::<(SwapArgs, ), (SwapResult, )>(
+            route.pool_canister_id, 
+            "swap", 
+            (SwapArgs {
+                amount_in: amount_in.to_string(),
+                zero_for_one: route.zero_for_one,
+                amount_out_minimum: min_amount_out.to_string(),
+            }, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        res.0.into_result()
+    }
+
+    // the caller's (amount in, amount out) token balances held by the pool
+    pub async fn unused_balance(
+        route: &SwapRoute,
+        caller: Principal
+    ) -> Result<(u128, u128), String> {
+        let res = ic_cdk::call::<(Principal, ), (UnusedBalanceResult, )>(
+            route.pool_canister_id, 
+            "getUserUnusedBalance", 
+            (caller, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        match res.0 {
+            UnusedBalanceResult::Ok(balance) => {
+                let (balance0, balance1) = (nat_to_u128(balance.balance0), nat_to_u128(balance.balance1));
+                Ok(if route.zero_for_one { (balance0, balance1) } else { (balance1, balance0) })
+            },
+            UnusedBalanceResult::Err(err) => {
+                Err(format!("{:?}", err))
+            }
+        }
+    }
+
+    // sends the amount, minus the fee, from the caller's unused balance to the caller's main account
+    pub async fn withdraw(
+        route: &SwapRoute,
+        ledger: Principal,
+        amount: u128,
+        fee: u128
+    ) -> Result<u128, String> {
+        let res = ic_cdk::call::<(WithdrawArgs, ), (SwapResult, )>(
+            route.pool_canister_id, 
+            "withdraw", 
+            (WithdrawArgs {
+                token: ledger.to_text(),
+                amount: amount.into(),
+                fee: fee.into(),
+            }, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        res.0.into_result()
+    }
+}
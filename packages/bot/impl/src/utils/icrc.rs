@@ -0,0 +1,63 @@
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::{
+    account::{Account, Subaccount}, 
+    transfer::{TransferArg, TransferError}
+};
+use super::nat::nat_to_u128;
+
+pub struct Icrc;
+
+impl Icrc {
+    pub async fn balance_of(
+        ledger: Principal,
+        account: Account
+    ) -> Result<u128, String> {
+        let res = ic_cdk::call::<(Account, ), (Nat, )>(
+            ledger, 
+            "icrc1_balance_of", 
+            (account, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        Ok(nat_to_u128(res.0))
+    }
+
+    pub async fn fee(
+        ledger: Principal
+    ) -> Result<u128, String> {
+        let res = ic_cdk::call::<(), (Nat, )>(
+            ledger, 
+            "icrc1_fee", 
+            ()
+        ).await
+            .map_err(|e| e.1)?;
+
+        Ok(nat_to_u128(res.0))
+    }
+
+    // the fee is paid by the sender, on top of the amount
+    pub async fn transfer(
+        ledger: Principal,
+        from_subaccount: Option<Subaccount>,
+        to: Account,
+        amount: u128
+    ) -> Result<u64, String> {
+        let res = ic_cdk::call::<(TransferArg, ), (Result<Nat, TransferError>, )>(
+            ledger, 
+            "icrc1_transfer", 
+            (TransferArg {
+                from_subaccount,
+                to,
+                fee: None,
+                created_at_time: Some(ic_cdk::api::time()),
+                memo: None,
+                amount: amount.into(),
+            }, )
+        ).await
+            .map_err(|e| e.1)?
+            .0
+            .map_err(|e| format!("{:?}", e))?;
+
+        Ok(nat_to_u128(res) as u64)
+    }
+}
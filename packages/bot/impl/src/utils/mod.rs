@@ -1,3 +1,9 @@
 pub mod ic;
 pub mod cmc;
-pub mod nat;
\ No newline at end of file
+pub mod nat;
+pub mod chat;
+pub mod chunks;
+pub mod icp_index;
+pub mod icrc;
+pub mod icpswap;
+pub mod decimal;
\ No newline at end of file
@@ -0,0 +1,31 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use crate::{types::job::{JobDelivery, JobDestination, JobState, JobType}, updates::add_job::JobId};
+
+#[derive(Serialize, Deserialize, CandidType)]
+pub struct GetJobArgs {
+    pub job_id: JobId,
+}
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct JobDetails {
+    pub id: JobId,
+    pub ty: JobType,
+    pub output_template: String,
+    pub filter: Option<String>,
+    pub interval: u32,
+    pub batch_size: u32,
+    pub state: JobState,
+    pub offset: u64,
+    pub last_run_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub next_due_at: Option<u64>,
+    pub failures: u32,
+    pub max_failures: u32,
+    pub destination: Option<JobDestination>,
+    pub delivery: JobDelivery,
+    // events waiting to be posted in a digest
+    pub pending: u32,
+}
+
+pub type GetJobResult = Result<JobDetails, String>;
@@ -0,0 +1,27 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use crate::updates::add_job::JobId;
+
+#[derive(Serialize, Deserialize, CandidType)]
+pub struct GetJobRunsArgs {
+    pub job_id: JobId,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct JobRun {
+    pub timestamp: u64,
+    pub fetched: u32,
+    pub posted: u32,
+    pub cycles: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct JobRuns {
+    pub runs: Vec<JobRun>,
+    pub total: u32,
+}
+
+pub type GetJobRunsResult = Result<JobRuns, String>;
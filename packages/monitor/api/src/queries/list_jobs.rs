@@ -1,7 +1,7 @@
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
-use crate::{types::job::{JobState, JobType}, updates::add_job::JobId};
+use crate::{types::job::{JobDestination, JobState, JobType}, updates::add_job::JobId};
 
 #[derive(Serialize, Deserialize, CandidType)]
 pub struct ListJobsArgs {
@@ -14,8 +14,10 @@ pub struct Job {
     pub id: JobId,
     pub ty: JobType,
     pub output_template: String,
+    pub filter: Option<String>,
     pub interval: u32,
     pub state: JobState,
+    pub destination: Option<JobDestination>,
 }
 
 pub type ListJobsResult = Result<Vec<Job>, String>;
\ No newline at end of file
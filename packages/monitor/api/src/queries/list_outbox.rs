@@ -0,0 +1,23 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use crate::updates::add_job::JobId;
+
+#[derive(Serialize, Deserialize, CandidType)]
+pub struct ListOutboxArgs {
+    pub offset: u32,
+    pub size: u32,
+    pub dead: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct OutboxEntry {
+    pub id: u64,
+    pub job_id: JobId,
+    pub messages: Vec<String>,
+    pub created_at: u64,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+}
+
+pub type ListOutboxResult = Result<Vec<OutboxEntry>, String>;
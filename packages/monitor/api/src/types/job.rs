@@ -1,6 +1,7 @@
 use std::fmt::Display;
 use candid::{CandidType, Principal};
 use serde::{Deserialize, Serialize};
+use super::nns::{status_name, topic_name};
 
 #[derive(Clone, Serialize, Deserialize, CandidType)]
 pub struct JobCanister {
@@ -8,9 +9,84 @@ pub struct JobCanister {
     pub method_name: String,
 }
 
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct JobIcrc3Ledger {
+    pub canister_id: Principal,
+}
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct JobIcpLedger {
+    pub canister_id: Principal,
+    pub accounts: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct JobSnsProposals {
+    pub governance_canister_id: Principal,
+}
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct JobNnsProposals {
+    pub governance_canister_id: Principal,
+    pub topics: Vec<i32>,
+    pub excluded_statuses: Vec<i32>,
+}
+
+// a group or a channel of a community, where the bot holds an API key
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, CandidType)]
+pub enum JobDestination {
+    Group(Principal),
+    Channel(Principal, u32),
+}
+
+impl Display for JobDestination {
+    fn fmt(
+        &self, 
+        fmt: &mut std::fmt::Formatter<'_>
+    ) -> std::fmt::Result {
+        match self {
+            JobDestination::Group(canister_id) => fmt.write_fmt(format_args!("group {}", canister_id)),
+            JobDestination::Channel(canister_id, channel_id) => fmt.write_fmt(format_args!("channel {}/{}", canister_id, channel_id)),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub enum JobDelivery {
+    // each batch is posted as soon as it's fetched
+    Immediate,
+    // events are accumulated and posted together every n minutes
+    Digest {
+        every: u32,
+        header: Option<String>,
+        footer: Option<String>,
+    },
+    // events are accumulated until there are n of them
+    Threshold {
+        events: u32,
+    },
+}
+
+impl Display for JobDelivery {
+    fn fmt(
+        &self, 
+        fmt: &mut std::fmt::Formatter<'_>
+    ) -> std::fmt::Result {
+        match self {
+            JobDelivery::Immediate => fmt.write_str("immediate"),
+            JobDelivery::Digest { every, .. } => fmt.write_fmt(format_args!("digest every {} min", every)),
+            JobDelivery::Threshold { events } => fmt.write_fmt(format_args!("after {} events", events)),
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, CandidType)]
 pub enum JobType {
-    Canister(JobCanister)
+    Canister(JobCanister),
+    Icrc3Ledger(JobIcrc3Ledger),
+    IcpLedger(JobIcpLedger),
+    SnsProposals(JobSnsProposals),
+    NnsProposals(JobNnsProposals),
 }
 
 impl Display for JobType {
@@ -22,6 +98,37 @@ impl Display for JobType {
             JobType::Canister(can) => {
                 format!("Canister(id:{}, method:{})", can.canister_id.to_text(), can.method_name)
             },
+            JobType::Icrc3Ledger(ledger) => {
+                format!("Icrc3Ledger(id:{})", ledger.canister_id.to_text())
+            },
+            JobType::IcpLedger(ledger) => {
+                if ledger.accounts.is_empty() {
+                    format!("IcpLedger(id:{})", ledger.canister_id.to_text())
+                }
+                else {
+                    format!("IcpLedger(id:{}, accounts:{})", ledger.canister_id.to_text(), ledger.accounts.join(","))
+                }
+            },
+            JobType::SnsProposals(sns) => {
+                format!("SnsProposals(governance:{})", sns.governance_canister_id.to_text())
+            },
+            JobType::NnsProposals(nns) => {
+                let mut s = format!("NnsProposals(governance:{}", nns.governance_canister_id.to_text());
+                if !nns.topics.is_empty() {
+                    s.push_str(&format!(
+                        ", topics:{}", 
+                        nns.topics.iter().map(|t| topic_name(*t)).collect::<Vec<_>>().join(",")
+                    ));
+                }
+                if !nns.excluded_statuses.is_empty() {
+                    s.push_str(&format!(
+                        ", excluded:{}", 
+                        nns.excluded_statuses.iter().map(|t| status_name(*t)).collect::<Vec<_>>().join(",")
+                    ));
+                }
+                s.push(')');
+                s
+            },
         };
 
         fmt.write_fmt(format_args!("{}", s))
@@ -32,6 +139,10 @@ impl Display for JobType {
 pub enum JobState {
     Idle,
     Running,
+    Failed {
+        reason: String,
+        since: u64,
+    },
 }
 
 impl Display for JobState {
@@ -39,11 +150,10 @@ impl Display for JobState {
         &self, 
         fmt: &mut std::fmt::Formatter<'_>
     ) -> std::fmt::Result {
-        let s = match self {
-            JobState::Idle => "idle",
-            JobState::Running => "running",
-        };
-
-        fmt.write_fmt(format_args!("{}", s))
+        match self {
+            JobState::Idle => fmt.write_str("idle"),
+            JobState::Running => fmt.write_str("running"),
+            JobState::Failed { reason, .. } => fmt.write_fmt(format_args!("failed ({})", reason)),
+        }
     }
 }
\ No newline at end of file
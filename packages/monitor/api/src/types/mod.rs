@@ -1 +1,2 @@
-pub mod job;
\ No newline at end of file
+pub mod job;
+pub mod nns;
\ No newline at end of file
@@ -0,0 +1,63 @@
+pub const NNS_TOPICS: &[(i32, &str)] = &[
+    (0, "Unspecified"),
+    (1, "NeuronManagement"),
+    (2, "ExchangeRate"),
+    (3, "NetworkEconomics"),
+    (4, "Governance"),
+    (5, "NodeAdmin"),
+    (6, "ParticipantManagement"),
+    (7, "SubnetManagement"),
+    (8, "NetworkCanisterManagement"),
+    (9, "Kyc"),
+    (10, "NodeProviderRewards"),
+    (11, "SnsDecentralizationSale"),
+    (12, "IcOsVersionElection"),
+    (13, "IcOsVersionDeployment"),
+    (14, "SnsAndCommunityFund"),
+    (15, "ApiBoundaryNodeManagement"),
+    (16, "SubnetRental"),
+    (17, "ProtocolCanisterManagement"),
+    (18, "ServiceNervousSystemManagement"),
+];
+
+pub const NNS_PROPOSAL_STATUSES: &[(i32, &str)] = &[
+    (1, "Open"),
+    (2, "Rejected"),
+    (3, "Adopted"),
+    (4, "Executed"),
+    (5, "Failed"),
+];
+
+pub fn topic_name(
+    topic: i32
+) -> String {
+    NNS_TOPICS.iter()
+        .find(|(id, _)| *id == topic)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| topic.to_string())
+}
+
+pub fn topic_id(
+    name: &str
+) -> Option<i32> {
+    NNS_TOPICS.iter()
+        .find(|(_, n)| n.eq_ignore_ascii_case(name))
+        .map(|(id, _)| *id)
+}
+
+pub fn status_name(
+    status: i32
+) -> String {
+    NNS_PROPOSAL_STATUSES.iter()
+        .find(|(id, _)| *id == status)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| status.to_string())
+}
+
+pub fn status_id(
+    name: &str
+) -> Option<i32> {
+    NNS_PROPOSAL_STATUSES.iter()
+        .find(|(_, n)| n.eq_ignore_ascii_case(name))
+        .map(|(id, _)| *id)
+}
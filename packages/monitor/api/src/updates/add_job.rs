@@ -1,16 +1,20 @@
-use candid::{CandidType, Principal};
+use candid::CandidType;
 use serde::{Deserialize, Serialize};
+use crate::types::job::{JobDelivery, JobDestination, JobType};
 
 pub type JobId = u64;
 
 #[derive(Serialize, Deserialize, CandidType)]
 pub struct AddJobArgs {
-    pub canister_id: Principal, 
-    pub method_name: String, 
+    pub ty: JobType,
     pub interval: u32,
     pub batch_size: u32,
     pub output_template: String, 
-    pub offset: u32,
+    pub filter: Option<String>,
+    pub max_failures: Option<u32>,
+    pub destination: Option<JobDestination>,
+    pub delivery: Option<JobDelivery>,
+    pub offset: Option<u64>,
 }
 
 pub type AddJobResult = Result<JobId, String>;
\ No newline at end of file
@@ -0,0 +1,10 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, CandidType)]
+pub struct DropOutboxArgs {
+    pub id: Option<u64>,
+    pub dead: bool,
+}
+
+pub type DropOutboxResult = Result<u32, String>;
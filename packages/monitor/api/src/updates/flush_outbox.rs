@@ -0,0 +1 @@
+pub type FlushOutboxResult = Result<u32, String>;
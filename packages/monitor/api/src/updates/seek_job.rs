@@ -0,0 +1,18 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use super::add_job::JobId;
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub enum SeekPosition {
+    Absolute(u64),
+    Latest,
+    Back(u64),
+}
+
+#[derive(Serialize, Deserialize, CandidType)]
+pub struct SeekJobArgs {
+    pub job_id: JobId, 
+    pub position: SeekPosition,
+}
+
+pub type SeekJobResult = Result<u64, String>;
@@ -0,0 +1,19 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use crate::types::job::{JobDelivery, JobDestination};
+use super::add_job::JobId;
+
+#[derive(Serialize, Deserialize, CandidType)]
+pub struct UpdateJobArgs {
+    pub job_id: JobId, 
+    pub interval: Option<u32>,
+    pub batch_size: Option<u32>,
+    pub output_template: Option<String>,
+    pub filter: Option<String>,
+    pub max_failures: Option<u32>,
+    // Some(None) posts to the monitor's chat again
+    pub destination: Option<Option<JobDestination>>,
+    pub delivery: Option<JobDelivery>,
+}
+
+pub type UpdateJobResult = Result<(), String>;
@@ -0,0 +1 @@
+pub type WithdrawCyclesResult = Result<u128, String>;
@@ -17,8 +17,17 @@ use monitor_api::{
         del_job::*,
         start_job::*,
         stop_job::*,
+        update_job::*,
+        seek_job::*,
+        flush_outbox::*,
+        drop_outbox::*,
     },
-    queries::list_jobs::*
+    queries::{
+        list_jobs::*,
+        get_job::*,
+        list_outbox::*,
+        get_job_runs::*,
+    }
 };
 
 ic_cdk::export_candid!();
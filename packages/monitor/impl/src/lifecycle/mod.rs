@@ -1,6 +1,7 @@
-use crate::{services::manager::manager::JobManager, 
-    state::{self, State}}
-;
+use crate::{
+    services::{manager::manager::JobManager, outbox::outbox::OutboxService}, 
+    state::{self, State}
+};
 
 pub mod init;
 pub mod post_upgrade;
@@ -14,6 +15,7 @@ pub(crate) fn setup(
     state::init(state);
 
     JobManager::start_if_required();
+    OutboxService::start_if_required();
 
     Ok(())
 }
@@ -5,6 +5,10 @@ use ic_stable_structures::{
 
 const UPGRADES: MemoryId            = MemoryId::new(0);
 const JOBS: MemoryId                = MemoryId::new(1);
+const OUTBOX: MemoryId              = MemoryId::new(2);
+const DEAD_LETTERS: MemoryId        = MemoryId::new(3);
+const JOB_RUNS: MemoryId            = MemoryId::new(4);
+const JOB_DIGESTS: MemoryId         = MemoryId::new(5);
 
 pub type Memory = VirtualMemory<DefaultMemoryImpl>;
 
@@ -24,3 +28,19 @@ pub fn get_upgrades_memory() -> Memory {
 pub fn get_jobs_memory() -> Memory {
     get_memory(JOBS)
 }
+
+pub fn get_outbox_memory() -> Memory {
+    get_memory(OUTBOX)
+}
+
+pub fn get_dead_letters_memory() -> Memory {
+    get_memory(DEAD_LETTERS)
+}
+
+pub fn get_job_runs_memory() -> Memory {
+    get_memory(JOB_RUNS)
+}
+
+pub fn get_job_digests_memory() -> Memory {
+    get_memory(JOB_DIGESTS)
+}
@@ -0,0 +1,9 @@
+use monitor_api::queries::get_job::{GetJobArgs, GetJobResult};
+use crate::{guards::*, services::manager::manager::JobManager};
+
+#[ic_cdk::query(guard = "owner_only")]
+pub fn get_job(
+    args: GetJobArgs
+) -> GetJobResult {
+    JobManager::get(args.job_id)
+}
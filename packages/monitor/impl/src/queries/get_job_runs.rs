@@ -0,0 +1,9 @@
+use monitor_api::queries::get_job_runs::{GetJobRunsArgs, GetJobRunsResult};
+use crate::{guards::*, services::manager::manager::JobManager};
+
+#[ic_cdk::query(guard = "owner_only")]
+pub fn get_job_runs(
+    args: GetJobRunsArgs
+) -> GetJobRunsResult {
+    JobManager::runs(args.job_id, args.offset as _, args.size as _)
+}
@@ -0,0 +1,11 @@
+use monitor_api::queries::list_outbox::{ListOutboxArgs, ListOutboxResult};
+use crate::{guards::*, services::outbox::outbox::OutboxService};
+
+#[ic_cdk::query(guard = "owner_only")]
+pub fn list_outbox(
+    args: ListOutboxArgs
+) -> ListOutboxResult {
+    Ok(
+        OutboxService::list(args.offset as _, args.size as _, args.dead)
+    )
+}
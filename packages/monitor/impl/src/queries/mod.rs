@@ -1 +1,4 @@
-pub mod list_jobs;
\ No newline at end of file
+pub mod list_jobs;
+pub mod get_job;
+pub mod list_outbox;
+pub mod get_job_runs;
\ No newline at end of file
@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+use icrc_ledger_types::icrc::generic_value::Value;
+use monitor_api::types::job::JobCanister;
+use crate::types::job::Job;
+
+pub struct CanisterFetcher;
+
+impl CanisterFetcher {
+    pub async fn fetch(
+        can: &JobCanister,
+        job: &mut Job
+    ) -> Result<(Vec<BTreeMap<String, Value>>, bool), String> {
+        ic_cdk::println!("info: quering canister {}.{}", can.canister_id, can.method_name);
+        // the canister's interface only takes u32 offsets
+        let offset = u32::try_from(job.offset())
+            .map_err(|_| format!("offset {} out of range", job.offset()))?;
+
+        let res = ic_cdk::call::<(u32, u32), (Result<(Vec<BTreeMap<String, Value>>, u32), String>, )>(
+            can.canister_id, 
+            &can.method_name, 
+            (offset, job.batch_size)
+        ).await
+            .map_err(|e| e.1)?
+            .0?;
+
+        let events = res.0;
+
+        let next = offset as u64 + events.len() as u64;
+        job.set_offset(next);
+
+        Ok((events, next < res.1 as u64))
+    }
+
+    pub async fn get_current_offset(
+        can: &JobCanister
+    ) -> Result<u32, String> {
+        let res = ic_cdk::call::<(u32, u32), (Result<(Vec<BTreeMap<String, Value>>, u32), String>, )>(
+            can.canister_id, 
+            &can.method_name, 
+            (0, 1)
+        ).await
+            .map_err(|e| e.1)?
+            .0?;
+
+        Ok(res.1)
+    }
+}
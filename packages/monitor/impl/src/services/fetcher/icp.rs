@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use candid::Nat;
+use ic_ledger_types::{
+    query_archived_blocks, query_blocks, AccountIdentifier,
+    Block, BlockIndex, GetBlocksArgs, Operation
+};
+use icrc_ledger_types::icrc::generic_value::Value;
+use monitor_api::types::job::JobIcpLedger;
+use crate::types::job::Job;
+
+pub struct IcpFetcher;
+
+impl IcpFetcher {
+    pub async fn fetch(
+        ledger: &JobIcpLedger,
+        job: &mut Job
+    ) -> Result<(Vec<BTreeMap<String, Value>>, bool), String> {
+        ic_cdk::println!("info: quering ledger {}.query_blocks", ledger.canister_id);
+        let res = query_blocks(
+            ledger.canister_id,
+            GetBlocksArgs {
+                start: job.offset(),
+                length: job.batch_size as _,
+            }
+        ).await
+            .map_err(|e| e.1)?;
+
+        let mut blocks = vec![];
+        for range in res.archived_blocks {
+            let archived = query_archived_blocks(
+                &range.callback,
+                GetBlocksArgs {
+                    start: range.start,
+                    length: range.length,
+                }
+            ).await
+                .map_err(|e| format!("calling archive: {}", e.1))?
+                .map_err(|e| format!("calling archive: {:?}", e))?;
+
+            blocks.extend(
+                archived.blocks.into_iter()
+                    .enumerate()
+                    .map(|(i, block)| (range.start + i as BlockIndex, block))
+            );
+        }
+
+        blocks.extend(
+            res.blocks.into_iter()
+                .enumerate()
+                .map(|(i, block)| (res.first_block_index + i as BlockIndex, block))
+        );
+
+        blocks.sort_by_key(|(index, _)| *index);
+
+        let accounts = ledger.accounts.iter()
+            .map(|acc| acc.to_lowercase())
+            .collect::<Vec<_>>();
+
+        // only consume contiguous blocks, so a missing range is retried on the next run
+        let mut events = vec![];
+        let mut next = job.offset();
+        for (index, block) in blocks {
+            if index < next {
+                continue;
+            }
+            else if index > next {
+                break;
+            }
+
+            next += 1;
+
+            let event = Self::block_to_event(index, block);
+            if accounts.is_empty() || Self::touches(&event, &accounts) {
+                events.push(event);
+            }
+        }
+
+        if next == job.offset() && next < res.chain_length {
+            return Err(format!("block {} not available", next));
+        }
+
+        job.set_offset(next);
+
+        Ok((events, next < res.chain_length))
+    }
+
+    pub async fn get_current_offset(
+        ledger: &JobIcpLedger
+    ) -> Result<u64, String> {
+        let res = query_blocks(
+            ledger.canister_id,
+            GetBlocksArgs {
+                start: 0,
+                length: 0,
+            }
+        ).await
+            .map_err(|e| e.1)?;
+
+        Ok(res.chain_length)
+    }
+
+    fn touches(
+        event: &BTreeMap<String, Value>,
+        accounts: &[String]
+    ) -> bool {
+        ["from", "to", "spender"].iter()
+            .filter_map(|key| match event.get(*key) {
+                Some(Value::Text(acc)) => Some(acc),
+                _ => None,
+            })
+            .any(|acc| accounts.contains(acc))
+    }
+
+    fn block_to_event(
+        index: BlockIndex,
+        block: Block
+    ) -> BTreeMap<String, Value> {
+        let mut event = BTreeMap::from([
+            ("index".to_string(), Value::Nat64(index)),
+            ("timestamp".to_string(), Value::Nat64(block.timestamp.timestamp_nanos)),
+            ("created_at_time".to_string(), Value::Nat64(block.transaction.created_at_time.timestamp_nanos)),
+            ("memo".to_string(), Value::Nat64(block.transaction.memo.0)),
+        ]);
+
+        if let Some(icrc1_memo) = block.transaction.icrc1_memo {
+            event.insert("icrc1_memo".to_string(), Value::Blob(icrc1_memo));
+        }
+
+        let account = |acc: AccountIdentifier| Value::Text(acc.to_hex());
+        let amount = |e8s: u64| Value::Nat(Nat::from(e8s));
+
+        let fields = match block.transaction.operation {
+            Some(Operation::Mint { to, amount: amt }) => vec![
+                ("op", Value::Text("mint".to_string())),
+                ("to", account(to)),
+                ("amount", amount(amt.e8s())),
+            ],
+            Some(Operation::Burn { from, amount: amt, .. }) => vec![
+                ("op", Value::Text("burn".to_string())),
+                ("from", account(from)),
+                ("amount", amount(amt.e8s())),
+            ],
+            Some(Operation::Transfer { from, to, amount: amt, fee, .. }) => vec![
+                ("op", Value::Text("transfer".to_string())),
+                ("from", account(from)),
+                ("to", account(to)),
+                ("amount", amount(amt.e8s())),
+                ("fee", amount(fee.e8s())),
+            ],
+            Some(Operation::Approve { from, spender, fee, .. }) => vec![
+                ("op", Value::Text("approve".to_string())),
+                ("from", account(from)),
+                ("spender", account(spender)),
+                ("fee", amount(fee.e8s())),
+            ],
+            Some(Operation::TransferFrom { from, to, spender, amount: amt, fee }) => vec![
+                ("op", Value::Text("transfer_from".to_string())),
+                ("from", account(from)),
+                ("to", account(to)),
+                ("spender", account(spender)),
+                ("amount", amount(amt.e8s())),
+                ("fee", amount(fee.e8s())),
+            ],
+            None => vec![],
+        };
+
+        for (key, value) in fields {
+            event.insert(key.to_string(), value);
+        }
+
+        event
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc::generic_value::Value;
+use monitor_api::types::job::JobIcrc3Ledger;
+use crate::{
+    types::{
+        icrc3::{BlockWithId, GetBlocksArgs, GetBlocksResult}, 
+        job::Job
+    }, 
+    utils::nat::nat_to_u64
+};
+
+pub struct Icrc3Fetcher;
+
+impl Icrc3Fetcher {
+    pub async fn fetch(
+        ledger: &JobIcrc3Ledger,
+        job: &mut Job
+    ) -> Result<(Vec<BTreeMap<String, Value>>, bool), String> {
+        ic_cdk::println!("info: quering ledger {}.icrc3_get_blocks", ledger.canister_id);
+        let res = Self::get_blocks(
+            ledger.canister_id, 
+            job.offset(), 
+            job.batch_size as u64
+        ).await?;
+
+        let log_length = nat_to_u64(&res.log_length);
+
+        let mut blocks = res.blocks;
+        for archived in res.archived_blocks {
+            let archived_res = ic_cdk::call::<(Vec<GetBlocksArgs>, ), (GetBlocksResult, )>(
+                archived.callback.0.principal, 
+                &archived.callback.0.method, 
+                (archived.args, )
+            ).await
+                .map_err(|e| format!("calling archive {}: {}", archived.callback.0.principal, e.1))?
+                .0;
+
+            blocks.extend(archived_res.blocks);
+        }
+
+        blocks.sort_by_key(|b| nat_to_u64(&b.id));
+
+        // only consume contiguous blocks, so a missing range is retried on the next run
+        let mut events = vec![];
+        let mut next = job.offset();
+        for block in blocks {
+            let id = nat_to_u64(&block.id);
+            if id < next {
+                continue;
+            }
+            else if id > next {
+                break;
+            }
+
+            events.push(Self::block_to_event(block));
+            next += 1;
+        }
+
+        if events.is_empty() && next < log_length {
+            return Err(format!("block {} not available", next));
+        }
+
+        job.set_offset(next);
+
+        Ok((events, next < log_length))
+    }
+
+    pub async fn get_current_offset(
+        ledger: &JobIcrc3Ledger
+    ) -> Result<u64, String> {
+        let res = Self::get_blocks(ledger.canister_id, 0, 0).await?;
+
+        Ok(nat_to_u64(&res.log_length))
+    }
+
+    async fn get_blocks(
+        canister_id: Principal,
+        start: u64,
+        length: u64
+    ) -> Result<GetBlocksResult, String> {
+        let res = ic_cdk::call::<(Vec<GetBlocksArgs>, ), (GetBlocksResult, )>(
+            canister_id, 
+            "icrc3_get_blocks", 
+            (vec![GetBlocksArgs {
+                start: Nat::from(start),
+                length: Nat::from(length),
+            }], )
+        ).await
+            .map_err(|e| e.1)?;
+
+        Ok(res.0)
+    }
+
+    fn block_to_event(
+        block: BlockWithId
+    ) -> BTreeMap<String, Value> {
+        let mut event = match block.block {
+            Value::Map(map) => map,
+            other => BTreeMap::from([("block".to_string(), other)]),
+        };
+
+        event.insert("id".to_string(), Value::Nat(block.id));
+
+        // expose the transaction fields at the top level: {op}, {amt}, {from}, {to}...
+        if let Some(Value::Map(tx)) = event.get("tx").cloned() {
+            for (key, value) in tx {
+                event.entry(key).or_insert(value);
+            }
+        }
+
+        if !event.contains_key("op") {
+            if let Some(Value::Text(btype)) = event.get("btype") {
+                let op = btype.trim_start_matches(|c: char| c.is_ascii_digit()).to_string();
+                event.insert("op".to_string(), Value::Text(op));
+            }
+        }
+
+        event
+    }
+}
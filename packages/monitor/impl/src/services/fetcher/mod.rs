@@ -0,0 +1,6 @@
+pub mod canister;
+pub mod icrc3;
+pub mod icp;
+pub mod proposals;
+pub mod sns;
+pub mod nns;
\ No newline at end of file
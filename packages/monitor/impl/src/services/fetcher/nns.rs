@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use candid::Principal;
+use icrc_ledger_types::icrc::generic_value::Value;
+use monitor_api::types::{
+    job::JobNnsProposals, 
+    nns::{status_name, topic_name, NNS_TOPICS}
+};
+use crate::{
+    services::fetcher::proposals::ProposalTracker, 
+    types::{
+        job::Job, 
+        nns::{ListProposalInfo, ListProposalInfoResponse, ProposalId, ProposalInfo}, 
+        proposal::{ProposalSnapshot, ProposalStatus}
+    }
+};
+
+pub struct NnsFetcher;
+
+impl NnsFetcher {
+    pub async fn fetch(
+        nns: &JobNnsProposals,
+        job: &mut Job
+    ) -> Result<(Vec<BTreeMap<String, Value>>, bool), String> {
+        ic_cdk::println!("info: quering governance {}.list_proposals", nns.governance_canister_id);
+        let canister_id = nns.governance_canister_id;
+        let exclude_topic = Self::excluded_topics(&nns.topics);
+        let mut cursor = job.proposals.clone().unwrap_or_default();
+
+        let events = ProposalTracker::poll(
+            &mut cursor,
+            job.batch_size,
+            |before, limit| Self::list_proposals(canister_id, exclude_topic.clone(), before, limit),
+            |id| Self::get_proposal_info(canister_id, id)
+        ).await?;
+
+        job.proposals = Some(cursor);
+
+        // events are named after the status the proposal reached (created = open)
+        let excluded = nns.excluded_statuses.iter()
+            .map(|status| match status_name(*status).as_str() {
+                "Open" => "created".to_string(),
+                name => name.to_lowercase(),
+            })
+            .collect::<Vec<_>>();
+
+        // the governance canister only excludes the topics known to NNS_TOPICS,
+        // so the ones added later must be filtered out here
+        let topics = nns.topics.iter()
+            .map(|topic| topic_name(*topic))
+            .collect::<Vec<_>>();
+
+        let events = events.into_iter()
+            .filter(|event| match event.get("event") {
+                Some(Value::Text(kind)) => !excluded.contains(kind),
+                _ => true,
+            })
+            .filter(|event| topics.is_empty() || match event.get("topic") {
+                Some(Value::Text(topic)) => topics.contains(topic),
+                _ => false,
+            })
+            .collect();
+
+        Ok((events, false))
+    }
+
+    pub async fn get_current_offset(
+        nns: &JobNnsProposals
+    ) -> Result<u64, String> {
+        let proposals = Self::list_proposals(
+            nns.governance_canister_id, 
+            Self::excluded_topics(&nns.topics), 
+            None, 
+            1
+        ).await?;
+
+        Ok(proposals.first().map(|p| p.id).unwrap_or(0))
+    }
+
+    fn excluded_topics(
+        topics: &[i32]
+    ) -> Vec<i32> {
+        if topics.is_empty() {
+            vec![]
+        }
+        else {
+            NNS_TOPICS.iter()
+                .map(|(id, _)| *id)
+                .filter(|id| !topics.contains(id))
+                .collect()
+        }
+    }
+
+    async fn list_proposals(
+        canister_id: Principal,
+        exclude_topic: Vec<i32>,
+        before: Option<u64>,
+        limit: u32
+    ) -> Result<Vec<ProposalSnapshot>, String> {
+        let res = ic_cdk::call::<(ListProposalInfo, ), (ListProposalInfoResponse, )>(
+            canister_id, 
+            "list_proposals", 
+            (ListProposalInfo {
+                include_reward_status: vec![],
+                omit_large_fields: Some(true),
+                before_proposal: before.map(|id| ProposalId { id }),
+                limit,
+                exclude_topic,
+                include_all_manage_neuron_proposals: None,
+                include_status: vec![],
+            }, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        Ok(
+            res.0.proposal_info.into_iter()
+                .filter_map(Self::to_snapshot)
+                .collect()
+        )
+    }
+
+    async fn get_proposal_info(
+        canister_id: Principal,
+        id: u64
+    ) -> Result<Option<ProposalSnapshot>, String> {
+        let res = ic_cdk::call::<(u64, ), (Option<ProposalInfo>, )>(
+            canister_id, 
+            "get_proposal_info", 
+            (id, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        Ok(res.0.and_then(Self::to_snapshot))
+    }
+
+    fn to_snapshot(
+        info: ProposalInfo
+    ) -> Option<ProposalSnapshot> {
+        let id = info.id?.id;
+        let (yes, no, total) = info.latest_tally
+            .map(|t| (t.yes, t.no, t.total))
+            .unwrap_or_default();
+
+        let status = match info.status {
+            2 => ProposalStatus::Rejected,
+            3 => ProposalStatus::Adopted,
+            4 => ProposalStatus::Executed,
+            5 => ProposalStatus::Failed,
+            _ => ProposalStatus::Open,
+        };
+
+        let proposal = info.proposal;
+
+        Some(ProposalSnapshot {
+            id,
+            status,
+            title: proposal.as_ref().and_then(|p| p.title.clone()).unwrap_or_default(),
+            summary: proposal.as_ref().map(|p| p.summary.clone()).unwrap_or_default(),
+            url: proposal.as_ref().map(|p| p.url.clone()).unwrap_or_default(),
+            proposer: info.proposer.map(|n| n.id.to_string()),
+            topic: Some(topic_name(info.topic)),
+            action: None,
+            yes,
+            no,
+            total,
+            created_at: info.proposal_timestamp_seconds,
+            decided_at: info.decided_timestamp_seconds,
+            executed_at: info.executed_timestamp_seconds,
+            failed_at: info.failed_timestamp_seconds,
+        })
+    }
+}
@@ -0,0 +1,164 @@
+use std::{collections::BTreeMap, future::Future};
+use icrc_ledger_types::icrc::generic_value::Value;
+use crate::types::proposal::{
+    ProposalSnapshot, ProposalStage, ProposalStatus, ProposalsCursor
+};
+
+const MAX_PAGES: usize = 4;
+const MAX_PENDING_PROPOSALS: usize = 100;
+
+pub struct ProposalTracker;
+
+impl ProposalTracker {
+    // list: (before_proposal_id, limit) -> proposals, newest first, before the id (exclusive)
+    // get: proposal_id -> proposal
+    pub async fn poll<L, LF, G, GF>(
+        cursor: &mut ProposalsCursor,
+        limit: u32,
+        list: L,
+        get: G
+    ) -> Result<Vec<BTreeMap<String, Value>>, String>
+        where
+            L: Fn(Option<u64>, u32) -> LF,
+            LF: Future<Output = Result<Vec<ProposalSnapshot>, String>>,
+            G: Fn(u64) -> GF,
+            GF: Future<Output = Result<Option<ProposalSnapshot>, String>> {
+        let limit = limit.max(1);
+        let mut snapshots = BTreeMap::new();
+
+        // 1st: new proposals, oldest first, in windows of ids after the last one seen (the ids are sequential).
+        // A burst longer than MAX_PAGES windows is listed by the next polls
+        let head = list(None, 1).await?
+            .first()
+            .map(|p| p.id)
+            .unwrap_or(0);
+        let mut scanned = cursor.last_id;
+        for _ in 0..MAX_PAGES {
+            if scanned >= head {
+                break;
+            }
+
+            let end = (scanned + limit as u64).min(head);
+            for p in list(Some(end + 1), limit).await? {
+                if p.id > scanned && p.id <= end {
+                    snapshots.insert(p.id, p);
+                }
+            }
+
+            scanned = end;
+        }
+
+        // 2nd: proposals still waiting for a decision or an execution
+        let pending = cursor.pending.keys()
+            .filter(|id| !snapshots.contains_key(id))
+            .cloned()
+            .collect::<Vec<_>>();
+        for id in pending {
+            match get(id).await? {
+                Some(p) => {
+                    snapshots.insert(id, p);
+                },
+                None => {
+                    cursor.pending.remove(&id);
+                }
+            }
+        }
+
+        let mut events = vec![];
+        for (_, p) in snapshots {
+            Self::track(cursor, p, &mut events);
+        }
+
+        // also past the ids not listed (ie: filtered out by the governance canister)
+        cursor.last_id = cursor.last_id.max(scanned);
+
+        while cursor.pending.len() > MAX_PENDING_PROPOSALS {
+            cursor.pending.pop_first();
+        }
+
+        Ok(events)
+    }
+
+    fn track(
+        cursor: &mut ProposalsCursor,
+        p: ProposalSnapshot,
+        events: &mut Vec<BTreeMap<String, Value>>
+    ) {
+        let stage = if p.id > cursor.last_id {
+            cursor.last_id = p.id;
+            events.push(Self::to_event("created", &p));
+            ProposalStage::Open
+        }
+        else if let Some(stage) = cursor.pending.get(&p.id) {
+            *stage
+        }
+        else {
+            return;
+        };
+
+        if stage == ProposalStage::Open {
+            match p.status {
+                ProposalStatus::Open => {},
+                ProposalStatus::Rejected => {
+                    events.push(Self::to_event("rejected", &p));
+                },
+                _ => {
+                    events.push(Self::to_event("adopted", &p));
+                },
+            }
+        }
+
+        match p.status {
+            ProposalStatus::Open => {
+                cursor.pending.insert(p.id, ProposalStage::Open);
+            },
+            ProposalStatus::Adopted => {
+                cursor.pending.insert(p.id, ProposalStage::Adopted);
+            },
+            ProposalStatus::Rejected => {
+                cursor.pending.remove(&p.id);
+            },
+            ProposalStatus::Executed => {
+                events.push(Self::to_event("executed", &p));
+                cursor.pending.remove(&p.id);
+            },
+            ProposalStatus::Failed => {
+                events.push(Self::to_event("failed", &p));
+                cursor.pending.remove(&p.id);
+            },
+        }
+    }
+
+    fn to_event(
+        kind: &str,
+        p: &ProposalSnapshot
+    ) -> BTreeMap<String, Value> {
+        let mut event = BTreeMap::from([
+            ("event".to_string(), Value::Text(kind.to_string())),
+            (format!("is_{}", kind), Value::Nat64(1)),
+            ("id".to_string(), Value::Nat64(p.id)),
+            ("title".to_string(), Value::Text(p.title.clone())),
+            ("summary".to_string(), Value::Text(p.summary.clone())),
+            ("url".to_string(), Value::Text(p.url.clone())),
+            ("yes".to_string(), Value::Nat64(p.yes)),
+            ("no".to_string(), Value::Nat64(p.no)),
+            ("total".to_string(), Value::Nat64(p.total)),
+            ("created_at".to_string(), Value::Nat64(p.created_at)),
+            ("decided_at".to_string(), Value::Nat64(p.decided_at)),
+            ("executed_at".to_string(), Value::Nat64(p.executed_at)),
+            ("failed_at".to_string(), Value::Nat64(p.failed_at)),
+        ]);
+
+        if let Some(proposer) = &p.proposer {
+            event.insert("proposer".to_string(), Value::Text(proposer.clone()));
+        }
+        if let Some(topic) = &p.topic {
+            event.insert("topic".to_string(), Value::Text(topic.clone()));
+        }
+        if let Some(action) = &p.action {
+            event.insert("action".to_string(), Value::Text(action.clone()));
+        }
+
+        event
+    }
+}
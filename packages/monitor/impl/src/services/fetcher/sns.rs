@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use candid::Principal;
+use icrc_ledger_types::icrc::generic_value::Value;
+use monitor_api::types::job::JobSnsProposals;
+use crate::{
+    services::fetcher::proposals::ProposalTracker, 
+    types::{
+        job::Job, 
+        proposal::{ProposalSnapshot, ProposalStatus}, 
+        sns::{
+            GetProposal, GetProposalResponse, GetProposalResult, ListProposals, 
+            ListProposalsResponse, Percentage, ProposalData, ProposalId
+        }
+    }
+};
+
+// the governance canister's defaults, in basis points, for the proposals without their own
+const DEFAULT_MIN_YES_OF_TOTAL: u64 = 300;
+const DEFAULT_MIN_YES_OF_EXERCISED: u64 = 5_000;
+
+pub struct SnsFetcher;
+
+impl SnsFetcher {
+    pub async fn fetch(
+        sns: &JobSnsProposals,
+        job: &mut Job
+    ) -> Result<(Vec<BTreeMap<String, Value>>, bool), String> {
+        ic_cdk::println!("info: quering governance {}.list_proposals", sns.governance_canister_id);
+        let canister_id = sns.governance_canister_id;
+        let mut cursor = job.proposals.clone().unwrap_or_default();
+
+        let events = ProposalTracker::poll(
+            &mut cursor,
+            job.batch_size,
+            |before, limit| Self::list_proposals(canister_id, before, limit),
+            |id| Self::get_proposal(canister_id, id)
+        ).await?;
+
+        job.proposals = Some(cursor);
+
+        Ok((events, false))
+    }
+
+    pub async fn get_current_offset(
+        sns: &JobSnsProposals
+    ) -> Result<u64, String> {
+        let proposals = Self::list_proposals(sns.governance_canister_id, None, 1).await?;
+
+        Ok(proposals.first().map(|p| p.id).unwrap_or(0))
+    }
+
+    async fn list_proposals(
+        canister_id: Principal,
+        before: Option<u64>,
+        limit: u32
+    ) -> Result<Vec<ProposalSnapshot>, String> {
+        let res = ic_cdk::call::<(ListProposals, ), (ListProposalsResponse, )>(
+            canister_id, 
+            "list_proposals", 
+            (ListProposals {
+                exclude_type: vec![],
+                before_proposal: before.map(|id| ProposalId { id }),
+                limit,
+                include_reward_status: vec![],
+                include_status: vec![],
+            }, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        Ok(
+            res.0.proposals.into_iter()
+                .filter_map(Self::to_snapshot)
+                .collect()
+        )
+    }
+
+    async fn get_proposal(
+        canister_id: Principal,
+        id: u64
+    ) -> Result<Option<ProposalSnapshot>, String> {
+        let res = ic_cdk::call::<(GetProposal, ), (GetProposalResponse, )>(
+            canister_id, 
+            "get_proposal", 
+            (GetProposal {
+                proposal_id: Some(ProposalId { id }),
+            }, )
+        ).await
+            .map_err(|e| e.1)?;
+
+        match res.0.result {
+            Some(GetProposalResult::Proposal(data)) => Ok(Self::to_snapshot(data)),
+            Some(GetProposalResult::Error(err)) => {
+                ic_cdk::println!("error: getting proposal {}: {}", id, err.error_message);
+                Ok(None)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn to_snapshot(
+        data: ProposalData
+    ) -> Option<ProposalSnapshot> {
+        let id = data.id?.id;
+        let (yes, no, total) = data.latest_tally
+            .map(|t| (t.yes, t.no, t.total))
+            .unwrap_or_default();
+
+        // SNS proposals have no status field: an adopted proposal is executed or fails right after
+        // the decision, and while it's executing its outcome is the one of the tally when decided,
+        // checked against the proposal's own minimum yes proportions (critical proposals have higher ones)
+        let status = if data.decided_timestamp_seconds == 0 {
+            ProposalStatus::Open
+        }
+        else if data.executed_timestamp_seconds > 0 {
+            ProposalStatus::Executed
+        }
+        else if data.failed_timestamp_seconds > 0 {
+            ProposalStatus::Failed
+        }
+        else if Self::is_accepted(
+            yes, 
+            no, 
+            total, 
+            Self::basis_points(&data.minimum_yes_proportion_of_total, DEFAULT_MIN_YES_OF_TOTAL), 
+            Self::basis_points(&data.minimum_yes_proportion_of_exercised, DEFAULT_MIN_YES_OF_EXERCISED)
+        ) {
+            ProposalStatus::Adopted
+        }
+        else {
+            ProposalStatus::Rejected
+        };
+
+        let proposal = data.proposal;
+
+        Some(ProposalSnapshot {
+            id,
+            status,
+            title: proposal.as_ref().map(|p| p.title.clone()).unwrap_or_default(),
+            summary: proposal.as_ref().map(|p| p.summary.clone()).unwrap_or_default(),
+            url: proposal.as_ref().map(|p| p.url.clone()).unwrap_or_default(),
+            proposer: data.proposer.map(|n| hex::encode(n.id)),
+            topic: None,
+            action: Some(data.action.to_string()),
+            yes,
+            no,
+            total,
+            created_at: data.proposal_creation_timestamp_seconds,
+            decided_at: data.decided_timestamp_seconds,
+            executed_at: data.executed_timestamp_seconds,
+            failed_at: data.failed_timestamp_seconds,
+        })
+    }
+
+    fn basis_points(
+        percentage: &Option<Percentage>,
+        default: u64
+    ) -> u64 {
+        percentage.as_ref()
+            .and_then(|p| p.basis_points)
+            .unwrap_or(default)
+    }
+
+    // the governance canister's rule: enough yes votes of the total voting power,
+    // and strictly more than the minimum proportion of the votes exercised
+    fn is_accepted(
+        yes: u64,
+        no: u64,
+        total: u64,
+        min_yes_of_total: u64,
+        min_yes_of_exercised: u64
+    ) -> bool {
+        let (yes, no, total) = (yes as u128, no as u128, total as u128);
+
+        yes * 10_000 >= total * min_yes_of_total as u128 &&
+            yes * 10_000 > (yes + no) * min_yes_of_exercised as u128
+    }
+}
@@ -1,14 +1,25 @@
 use std::collections::BTreeMap;
-use bot_api::{updates::notify_events::{NotifiyEventsArgs, NotifiyEventsResponse}, NOTIFY_EVENT_COST};
-use candid::Principal;
+use bot_api::updates::notify_events::JobEvent;
 use icrc_ledger_types::icrc::generic_value::Value;
-use monitor_api::types::job::{JobState, JobType};
+use monitor_api::{
+    queries::{get_job::JobDetails, get_job_runs::{JobRun, JobRuns}}, 
+    types::job::{JobDelivery, JobState, JobType}, 
+    updates::{seek_job::SeekPosition, update_job::UpdateJobArgs}
+};
 use crate::{
+    services::{
+        fetcher::{
+            canister::CanisterFetcher, icp::IcpFetcher, 
+            icrc3::Icrc3Fetcher, nns::NnsFetcher, sns::SnsFetcher
+        }, 
+        outbox::outbox::OutboxService
+    }, 
     state, 
-    storage::job::job::JobStorage, 
+    storage::job::{digest::JobDigestStorage, job::JobStorage, run::JobRunStorage}, 
     types::{
-        active_job::ActiveJob, job::Job, scheduler::JobId
-    }
+        active_job::ActiveJob, condition::Condition, job::Job, scheduler::JobId, template::Template
+    }, 
+    utils::delivery::MAX_DIGEST_EVENTS
 };
 
 pub struct JobManager;
@@ -54,7 +65,7 @@ impl JobManager {
     ) -> Result<(), String> {
         if let Some(mut job) = JobStorage::load(job_id) {
             match job.state {
-                JobState::Idle => {
+                JobState::Idle | JobState::Failed { .. } => {
                     let now = ic_cdk::api::time() / 1_000_000;
                     state::mutate(|s| -> Result<(), String> {
                         let next_due = s.scheduler_mut()
@@ -77,6 +88,7 @@ impl JobManager {
                     })?;
 
                     job.state = JobState::Running;
+                    job.failures = None;
                     JobStorage::save(job_id, job);
                 }
                 _ => {}
@@ -113,6 +125,64 @@ impl JobManager {
         }
     }
 
+    pub fn update(
+        args: UpdateJobArgs
+    ) -> Result<(), String> {
+        let job_id = args.job_id;
+        if let Some(mut job) = JobStorage::load(job_id) {
+            if let Some(batch_size) = args.batch_size {
+                job.batch_size = batch_size;
+            }
+            if let Some(output_template) = args.output_template {
+                job.output_template = output_template;
+            }
+            if let Some(filter) = args.filter {
+                job.filter = if filter.is_empty() { None } else { Some(filter) };
+            }
+            if let Some(max_failures) = args.max_failures {
+                job.max_failures = Some(max_failures);
+            }
+            if let Some(destination) = args.destination {
+                job.destination = destination;
+            }
+            if let Some(delivery) = args.delivery {
+                job.delivery = Some(delivery);
+            }
+
+            match args.interval {
+                Some(interval) if interval != job.interval => {
+                    job.interval = interval;
+
+                    if let JobState::Running = job.state {
+                        let now = ic_cdk::api::time() / 1_000_000;
+                        state::mutate(|s| -> Result<(), String> {
+                            s.scheduler_mut()
+                                .reschedule(
+                                    job_id, 
+                                    ActiveJob {
+                                        interval,
+                                    }, 
+                                    now
+                                )?;
+
+                            s.scheduler().restart(Self::timer_cb);
+
+                            Ok(())
+                        })?;
+                    }
+                },
+                _ => {}
+            }
+
+            JobStorage::save(job_id, job);
+    
+            Ok(())
+        }
+        else {
+            Err(format!("Unknown job id: {}", job_id))
+        }
+    }
+
     pub fn delete(
         job_id: JobId
     ) -> Result<(), String> {
@@ -123,6 +193,8 @@ impl JobManager {
             });
 
             JobStorage::remove(job_id);
+            JobRunStorage::remove(job_id);
+            JobDigestStorage::remove(job_id);
 
             Ok(())
         }
@@ -141,25 +213,116 @@ impl JobManager {
                 id,
                 ty: job.ty,
                 output_template: job.output_template,
+                filter: job.filter,
                 interval: job.interval,
                 state: job.state,
+                destination: job.destination,
             })
             .collect()
     }
 
+    pub fn runs(
+        job_id: JobId,
+        offset: usize,
+        size: usize
+    ) -> Result<JobRuns, String> {
+        if JobStorage::exists(&job_id) {
+            let (runs, total) = JobRunStorage::list(job_id, offset, size);
+            Ok(JobRuns {
+                runs,
+                total: total as u32,
+            })
+        }
+        else {
+            Err(format!("Unknown job id: {}", job_id))
+        }
+    }
+
+    pub fn get(
+        job_id: JobId
+    ) -> Result<JobDetails, String> {
+        if let Some(job) = JobStorage::load(job_id) {
+            let next_due_at = state::read(|s| s.scheduler().next_due(job_id))
+                .map(|timestamp| timestamp * 1_000_000);
+            let max_failures = job.max_failures();
+            let delivery = job.delivery();
+            let pending = JobDigestStorage::load(job_id)
+                .map(|digest| digest.events.len() as u32)
+                .unwrap_or(0);
+
+            Ok(JobDetails {
+                id: job_id,
+                offset: job.position(),
+                ty: job.ty,
+                output_template: job.output_template,
+                filter: job.filter,
+                interval: job.interval,
+                batch_size: job.batch_size,
+                state: job.state,
+                last_run_at: job.last_run_at,
+                last_error: job.last_error,
+                next_due_at,
+                failures: job.failures.unwrap_or(0),
+                max_failures,
+                destination: job.destination,
+                delivery,
+                pending,
+            })
+        }
+        else {
+            Err(format!("Unknown job id: {}", job_id))
+        }
+    }
+
+    pub async fn seek(
+        job_id: JobId,
+        position: SeekPosition
+    ) -> Result<u64, String> {
+        let job = if let Some(job) = JobStorage::load(job_id) {
+            job
+        }
+        else {
+            return Err(format!("Unknown job id: {}", job_id));
+        };
+
+        let position = match position {
+            SeekPosition::Absolute(position) => position,
+            SeekPosition::Latest => Self::get_current_offset(&job.ty).await?,
+            SeekPosition::Back(n) => job.position().saturating_sub(n),
+        };
+
+        // reload, as the job may have changed during the call above
+        if let Some(mut job) = JobStorage::load(job_id) {
+            job.set_position(position);
+            JobStorage::save(job_id, job);
+            Ok(position)
+        }
+        else {
+            Err(format!("Unknown job id: {}", job_id))
+        }
+    }
+
     pub async fn get_current_offset(
-        canister_id: &Principal, 
-        method_name: &String, 
-    ) -> Result<u32, String> {
-        let res = ic_cdk::call::<(u32, u32), (Result<(Vec<BTreeMap<String, Value>>, u32), String>, )>(
-            canister_id.clone(), 
-            method_name, 
-            (0, 1)
-        ).await
-            .map_err(|e| e.1)?
-            .0?;
-
-        Ok(res.1)
+        ty: &JobType
+    ) -> Result<u64, String> {
+        match ty {
+            JobType::Canister(can) => {
+                CanisterFetcher::get_current_offset(can).await
+                    .map(|offset| offset as u64)
+            },
+            JobType::Icrc3Ledger(ledger) => {
+                Icrc3Fetcher::get_current_offset(ledger).await
+            },
+            JobType::IcpLedger(ledger) => {
+                IcpFetcher::get_current_offset(ledger).await
+            },
+            JobType::SnsProposals(sns) => {
+                SnsFetcher::get_current_offset(sns).await
+            },
+            JobType::NnsProposals(nns) => {
+                NnsFetcher::get_current_offset(nns).await
+            },
+        }
     }
 
     pub fn start_if_required(
@@ -184,85 +347,252 @@ impl JobManager {
         job_id: JobId
     ) {    
         if let Some(mut job) = JobStorage::load(job_id) {
-            match job.ty.clone() {
-                JobType::Canister(can) => {
-                    loop {
-                        match Self::query_canister(
-                            &can.canister_id, 
-                            &can.method_name, 
-                            &mut job
-                        ).await {
-                            Ok((messages, more_data)) => {
-                                if messages.len() > 0 {
-                                    if let Err(err) = Self::notify_events(messages).await {
-                                        ic_cdk::println!("error: notifying events: {}", err);    
-                                    }
-                                }
-                                if !more_data {
-                                    break;
-                                }
-                            }
-                            Err(err) => {
-                                ic_cdk::println!("error: calling {}.{}: {}", can.canister_id.to_text(), can.method_name, err);
-                                break;
-                            }
-                        };
+            let timestamp = ic_cdk::api::time();
+            let balance = ic_cdk::api::canister_balance128();
+            let mut last_error = None;
+            let mut fetched = 0;
+            let mut posted = 0;
+            loop {
+                match Self::fetch_events(job_id, &mut job).await {
+                    Ok((events, count, more_data)) => {
+                        fetched += count;
+                        posted += events.len();
+
+                        // queuing the events and saving the progress happen together, so no event is lost
+                        if events.len() > 0 {
+                            Self::queue_events(job_id, &job, events);
+                        }
+                        Self::save_progress(job_id, &job, None);
+
+                        // so a digest never grows past its cap while fetching
+                        Self::flush_digest_if_due(job_id);
+
+                        if !more_data {
+                            break;
+                        }
                     }
-                },
+                    Err(err) => {
+                        ic_cdk::println!("error: calling {}: {}", job.ty, err);
+                        last_error = Some(err);
+                        break;
+                    }
+                };
             }
 
-            JobStorage::save(job_id, job);
+            Self::save_progress(job_id, &job, last_error.clone());
+            Self::flush_digest_if_due(job_id);
+
+            let error = match (last_error, OutboxService::deliver().await) {
+                (Some(err), _) => Some(err),
+                (None, Some(err)) => Some(format!("delivering events: {}", err)),
+                (None, None) => None,
+            };
+
+            if JobStorage::exists(&job_id) {
+                JobRunStorage::push(job_id, JobRun {
+                    timestamp,
+                    fetched: fetched as u32,
+                    posted: posted as u32,
+                    cycles: balance.saturating_sub(ic_cdk::api::canister_balance128()) as u64,
+                    error,
+                });
+            }
         }
     }
 
-    async fn query_canister(
-        canister_id: &Principal, 
-        method_name: &String, 
-        job: &mut Job
-    ) -> Result<(Vec<String>, bool), String> {
-        
-        let mut messages = vec![];
-
-        ic_cdk::println!("info: quering canister {}.{}", canister_id, method_name);
-        let res = ic_cdk::call::<(u32, u32), (Result<(Vec<BTreeMap<String, Value>>, u32), String>, )>(
-            canister_id.clone(), 
-            method_name, 
-            (job.offset, job.batch_size)
-        ).await
-            .map_err(|e| e.1)?
-            .0?;
-
-        let events = res.0;
-
-        job.offset += events.len() as u32;
-
-        for event in events {
-            let mut text = job.output_template.clone();
-            for (key, value) in event.keys().zip(event.values()) {
-                text = text.replace(&format!("{{{}}}", key), &value.to_string());
+    fn queue_events(
+        job_id: JobId,
+        job: &Job,
+        events: Vec<JobEvent>
+    ) {
+        match job.delivery() {
+            JobDelivery::Immediate => {
+                OutboxService::push(job_id, events, job.destination.clone());
+            },
+            _ => {
+                JobDigestStorage::append(job_id, events, ic_cdk::api::time());
             }
-            messages.push(text);
         }
+    }
 
-        Ok((messages, job.offset < res.1))
+    // also posts the events left behind when the job is switched back to immediate delivery
+    fn flush_digest_if_due(
+        job_id: JobId
+    ) {
+        let job = match JobStorage::load(job_id) {
+            Some(job) => job,
+            None => return,
+        };
+
+        let digest = match JobDigestStorage::load(job_id) {
+            Some(digest) => digest,
+            None => return,
+        };
+
+        let now = ic_cdk::api::time();
+        let (due, header, footer) = match job.delivery() {
+            JobDelivery::Immediate => {
+                (true, None, None)
+            },
+            JobDelivery::Digest { every, header, footer } => {
+                (now >= digest.since + every as u64 * 60 * 1_000_000_000, header, footer)
+            },
+            JobDelivery::Threshold { events } => {
+                (digest.events.len() >= events as usize, None, None)
+            },
+        };
+
+        if !due && digest.events.len() < MAX_DIGEST_EVENTS {
+            return;
+        }
+
+        JobDigestStorage::remove(job_id);
+
+        // posted in digests of MAX_DIGEST_EVENTS at most, to fit in a call to the bot.
+        // If not due yet, the events left wait for the next one
+        let mut events = digest.events;
+        if !due {
+            let rest = events.split_off(events.len() - events.len() % MAX_DIGEST_EVENTS);
+            if !rest.is_empty() {
+                JobDigestStorage::append(job_id, rest, digest.since);
+            }
+        }
+
+        while !events.is_empty() {
+            let chunk = events.drain(..events.len().min(MAX_DIGEST_EVENTS)).collect::<Vec<_>>();
+
+            // variables available to the header and footer templates
+            let vars = BTreeMap::from([
+                ("job_id".to_string(), Value::Nat64(job_id)),
+                ("count".to_string(), Value::Nat64(chunk.len() as u64)),
+                ("since".to_string(), Value::Nat64(digest.since)),
+                ("until".to_string(), Value::Nat64(now)),
+            ]);
+            let render = |src: Option<String>| match Template::parse(&src?) {
+                Ok(template) => Some(template.render(&vars)),
+                Err(err) => {
+                    ic_cdk::println!("error: rendering digest of job {}: {}", job_id, err);
+                    None
+                }
+            };
+
+            OutboxService::push_digest(
+                job_id, 
+                chunk, 
+                job.destination.clone(), 
+                render(header.clone()), 
+                render(footer.clone())
+            );
+        }
     }
-    
-    async fn notify_events(
-        messages: Vec<String>
-    ) -> Result<(), String> {
-        let canister_id = state::read(|s| s.bot_canister_id().clone());
-        
-        ic_cdk::api::call::call_with_payment128::<(NotifiyEventsArgs, ), (NotifiyEventsResponse, )>(
-            canister_id, 
-            "notify_events", 
-            (NotifiyEventsArgs {
-                messages,
-            },),
-            NOTIFY_EVENT_COST as _
-        ).await
-            .map_err(|e| e.1)?
-            .0?;
-
-        Ok(())
+
+    fn save_progress(
+        job_id: JobId,
+        job: &Job,
+        last_error: Option<String>
+    ) {
+        // the job may have been edited or deleted while running, so only its progress is saved
+        if let Some(mut current) = JobStorage::load(job_id) {
+            current.set_offset(job.offset());
+            current.proposals = job.proposals.clone();
+            current.next_event = job.next_event;
+            current.last_run_at = Some(ic_cdk::api::time());
+
+            match &last_error {
+                Some(err) => {
+                    let failures = current.failures.unwrap_or(0) + 1;
+                    current.failures = Some(failures);
+
+                    let max_failures = current.max_failures();
+                    if max_failures > 0 && failures >= max_failures {
+                        if let JobState::Running = current.state {
+                            Self::suspend(job_id, &mut current, err, failures);
+                        }
+                    }
+                },
+                None => {
+                    current.failures = None;
+                }
+            }
+
+            current.last_error = last_error;
+            JobStorage::save(job_id, current);
+        }
+    }
+
+    fn suspend(
+        job_id: JobId,
+        job: &mut Job,
+        reason: &str,
+        failures: u32
+    ) {
+        ic_cdk::println!("error: job {} suspended after {} consecutive failures: {}", job_id, failures, reason);
+
+        state::mutate(|s| {
+            let _ = s.scheduler_mut()
+                .delete(job_id);
+        });
+
+        job.state = JobState::Failed {
+            reason: reason.to_string(),
+            since: ic_cdk::api::time(),
+        };
+
+        // the notice goes to the monitor's chat, where the job can be fixed
+        OutboxService::push(job_id, vec![JobEvent {
+            job_id,
+            index: job.next_event_index(),
+            fields: BTreeMap::new(),
+            text: format!(
+                "⚠️ Job {} ({}) was paused after {} consecutive failures. Last error: {}  \nFix the problem and start it again with `/eventmon job start {}`",
+                job_id, job.ty, failures, reason, job_id
+            ),
+        }], None);
+    }
+
+    // returns the events to post, the number of events fetched and if there's more data
+    async fn fetch_events(
+        job_id: JobId,
+        job: &mut Job
+    ) -> Result<(Vec<JobEvent>, usize, bool), String> {
+        let template = Template::parse(&job.output_template)?;
+        let filter = match &job.filter {
+            Some(filter) => Some(Condition::parse(filter)?),
+            None => None,
+        };
+
+        let (events, more_data) = match job.ty.clone() {
+            JobType::Canister(can) => {
+                CanisterFetcher::fetch(&can, job).await?
+            },
+            JobType::Icrc3Ledger(ledger) => {
+                Icrc3Fetcher::fetch(&ledger, job).await?
+            },
+            JobType::IcpLedger(ledger) => {
+                IcpFetcher::fetch(&ledger, job).await?
+            },
+            JobType::SnsProposals(sns) => {
+                SnsFetcher::fetch(&sns, job).await?
+            },
+            JobType::NnsProposals(nns) => {
+                NnsFetcher::fetch(&nns, job).await?
+            },
+        };
+
+        let count = events.len();
+        let events = events.into_iter()
+            .filter(|event| match &filter {
+                Some(filter) => filter.eval(event),
+                None => true,
+            })
+            .map(|event| JobEvent {
+                job_id,
+                index: job.next_event_index(),
+                text: template.render(&event),
+                fields: event,
+            })
+            .collect();
+
+        Ok((events, count, more_data))
     }
 }
\ No newline at end of file
@@ -0,0 +1 @@
+pub mod outbox;
\ No newline at end of file
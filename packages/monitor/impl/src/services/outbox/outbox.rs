@@ -0,0 +1,256 @@
+use std::{cell::Cell, collections::BTreeSet, time::Duration};
+use bot_api::{
+    updates::notify_events::{JobEvent, NotifiyEventsArgs, NotifiyEventsResponse, NOTIFY_EVENTS_VERSION}, 
+    NOTIFY_EVENT_COST
+};
+use ic_cdk_timers::TimerId;
+use monitor_api::{queries::list_outbox::OutboxEntry, types::job::JobDestination};
+use crate::{
+    state, 
+    storage::outbox::outbox::OutboxStorage, 
+    types::{outbox::{OutboxItem, OutboxItemId}, scheduler::JobId}
+};
+
+const MAX_ATTEMPTS: u32 = 8;
+const BASE_RETRY_DELAY: u64 = 30; // seconds
+const MAX_RETRY_DELAY: u64 = 3_600; // seconds
+const MAX_DELIVERY_TIME: u64 = 10 * 60 * 1_000_000_000; // 10 minutes
+
+thread_local! {
+    static DELIVERING_SINCE: Cell<Option<u64>> = Cell::default();
+    static RETRY_TIMER_ID: Cell<Option<TimerId>> = Cell::default();
+}
+
+pub struct OutboxService;
+
+impl OutboxService {
+    pub fn push(
+        job_id: JobId,
+        events: Vec<JobEvent>,
+        destination: Option<JobDestination>
+    ) -> OutboxItemId {
+        OutboxStorage::push(
+            OutboxItem::new(job_id, events, destination, ic_cdk::api::time())
+        )
+    }
+
+    pub fn push_digest(
+        job_id: JobId,
+        events: Vec<JobEvent>,
+        destination: Option<JobDestination>,
+        header: Option<String>,
+        footer: Option<String>
+    ) -> OutboxItemId {
+        let mut item = OutboxItem::new(job_id, events, destination, ic_cdk::api::time());
+        item.header = header;
+        item.footer = footer;
+        OutboxStorage::push(item)
+    }
+
+    pub fn start_if_required(
+    ) {
+        if OutboxStorage::count() > 0 {
+            Self::schedule_delivery(0);
+        }
+    }
+
+    // the items of each job are delivered in order, so a failing item only holds back
+    // the ones after it of the same job. returns the error of the last failed attempt, if any
+    pub async fn deliver(
+    ) -> Option<String> {
+        let now = ic_cdk::api::time();
+        // a trap after an await would leave the flag set, so it expires
+        if let Some(since) = DELIVERING_SINCE.get() {
+            if now < since + MAX_DELIVERY_TIME {
+                return None;
+            }
+        }
+
+        DELIVERING_SINCE.set(Some(now));
+
+        let mut last_error = None;
+        let mut waiting = BTreeSet::new();
+        let mut next_attempt_at: Option<u64> = None;
+        let mut last_id = None;
+
+        // items queued while waiting for the bot are picked up too, as they have higher ids
+        while let Some((id, mut item)) = OutboxStorage::next(last_id) {
+            last_id = Some(id);
+
+            if waiting.contains(&item.job_id) {
+                continue;
+            }
+
+            let now = ic_cdk::api::time();
+            if item.next_attempt_at > now {
+                waiting.insert(item.job_id);
+                next_attempt_at = Some(next_attempt_at.unwrap_or(u64::MAX).min(item.next_attempt_at));
+                continue;
+            }
+
+            let res = Self::notify_events(&item).await;
+
+            // the item could have been dropped while waiting for the bot
+            if OutboxStorage::remove(id).is_none() {
+                continue;
+            }
+
+            if let Err(err) = res {
+                ic_cdk::println!("error: notifying events: {}", err);
+                item.attempts += 1;
+                item.last_error = Some(err.clone());
+                last_error = Some(err);
+
+                if item.attempts >= MAX_ATTEMPTS {
+                    ic_cdk::println!("error: outbox item {} moved to the dead letters after {} attempts", id, item.attempts);
+                    OutboxStorage::save_dead(id, item);
+                }
+                else {
+                    let delay = (BASE_RETRY_DELAY << (item.attempts - 1)).min(MAX_RETRY_DELAY) * 1_000_000_000;
+                    item.next_attempt_at = now + delay;
+                    waiting.insert(item.job_id);
+                    next_attempt_at = Some(next_attempt_at.unwrap_or(u64::MAX).min(item.next_attempt_at));
+                    OutboxStorage::save(id, item);
+                }
+            }
+        }
+
+        if let Some(at) = next_attempt_at {
+            Self::schedule_delivery(at.saturating_sub(ic_cdk::api::time()));
+        }
+
+        DELIVERING_SINCE.set(None);
+
+        last_error
+    }
+
+    pub fn list(
+        offset: usize,
+        size: usize,
+        dead: bool
+    ) -> Vec<OutboxEntry> {
+        let items = if dead {
+            OutboxStorage::list_dead(offset, size)
+        }
+        else {
+            OutboxStorage::list(offset, size)
+        };
+
+        items.into_iter()
+            .map(|(id, item)| OutboxEntry {
+                id,
+                job_id: item.job_id,
+                messages: item.texts(),
+                created_at: item.created_at,
+                attempts: item.attempts,
+                next_attempt_at: item.next_attempt_at,
+                last_error: item.last_error,
+            })
+            .collect()
+    }
+
+    // retries the pending items now and gives the dead letters another round of attempts
+    pub fn flush(
+    ) -> u32 {
+        let now = ic_cdk::api::time();
+        let mut count = 0;
+
+        for id in OutboxStorage::ids() {
+            if let Some(mut item) = OutboxStorage::remove(id) {
+                item.next_attempt_at = now;
+                OutboxStorage::save(id, item);
+                count += 1;
+            }
+        }
+
+        // re-queued under a new id, so they go out after the items queued meanwhile
+        for id in OutboxStorage::dead_ids() {
+            if let Some(mut item) = OutboxStorage::remove_dead(id) {
+                item.attempts = 0;
+                item.next_attempt_at = now;
+                OutboxStorage::push(item);
+                count += 1;
+            }
+        }
+
+        if count > 0 {
+            Self::schedule_delivery(0);
+        }
+
+        count
+    }
+
+    pub fn drop(
+        id: Option<OutboxItemId>,
+        dead: bool
+    ) -> Result<u32, String> {
+        let ids = match id {
+            Some(id) => vec![id],
+            None if dead => OutboxStorage::dead_ids(),
+            None => OutboxStorage::ids(),
+        };
+
+        let mut count = 0;
+        for id in ids {
+            let item = if dead {
+                OutboxStorage::remove_dead(id)
+            }
+            else {
+                OutboxStorage::remove(id)
+            };
+
+            if item.is_some() {
+                count += 1;
+            }
+        }
+
+        match id {
+            Some(id) if count == 0 => Err(format!("Unknown outbox item id: {}", id)),
+            _ => Ok(count),
+        }
+    }
+
+    fn schedule_delivery(
+        delay: u64
+    ) {
+        if let Some(timer_id) = RETRY_TIMER_ID.get() {
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+
+        let timer_id = ic_cdk_timers::set_timer(
+            Duration::from_nanos(delay),
+            || {
+                RETRY_TIMER_ID.set(None);
+                ic_cdk::spawn(async {
+                    Self::deliver().await;
+                });
+            }
+        );
+
+        RETRY_TIMER_ID.set(Some(timer_id));
+    }
+
+    async fn notify_events(
+        item: &OutboxItem
+    ) -> Result<(), String> {
+        let canister_id = state::read(|s| s.bot_canister_id().clone());
+        
+        ic_cdk::api::call::call_with_payment128::<(NotifiyEventsArgs, ), (NotifiyEventsResponse, )>(
+            canister_id, 
+            "notify_events", 
+            (NotifiyEventsArgs {
+                version: Some(NOTIFY_EVENTS_VERSION),
+                messages: vec![],
+                events: Some(item.events.clone()),
+                header: item.header.clone(),
+                footer: item.footer.clone(),
+                destination: item.destination.clone(),
+            },),
+            NOTIFY_EVENT_COST as _
+        ).await
+            .map_err(|e| e.1)?
+            .0?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use std::cell::RefCell;
+use bot_api::updates::notify_events::JobEvent;
+use ic_stable_structures::BTreeMap;
+use crate::{
+    memory::{get_job_digests_memory, Memory}, 
+    types::{job_digest::JobDigest, scheduler::JobId}
+};
+
+pub struct JobDigestStorage;
+
+thread_local! {
+    static JOB_DIGESTS: RefCell<BTreeMap<JobId, JobDigest, Memory>> = RefCell::new(
+        BTreeMap::init(
+            get_job_digests_memory()
+        )
+    );
+}
+
+impl JobDigestStorage {
+    pub fn append(
+        id: JobId,
+        events: Vec<JobEvent>,
+        now: u64
+    ) {
+        JOB_DIGESTS.with_borrow_mut(|digests| {
+            let mut digest = digests.get(&id).unwrap_or_else(|| JobDigest {
+                events: vec![],
+                since: now,
+            });
+            digest.events.extend(events);
+            digests.insert(id, digest);
+        });
+    }
+
+    pub fn load(
+        id: JobId
+    ) -> Option<JobDigest> {
+        JOB_DIGESTS.with_borrow(|digests| {
+            digests.get(&id)
+        })
+    }
+
+    pub fn remove(
+        id: JobId
+    ) -> Option<JobDigest> {
+        JOB_DIGESTS.with_borrow_mut(|digests| {
+            digests.remove(&id)
+        })
+    }
+}
@@ -1 +1,3 @@
-pub mod job;
\ No newline at end of file
+pub mod job;
+pub mod run;
+pub mod digest;
\ No newline at end of file
@@ -0,0 +1,59 @@
+use std::cell::RefCell;
+use ic_stable_structures::BTreeMap;
+use monitor_api::queries::get_job_runs::JobRun;
+use crate::{
+    memory::{get_job_runs_memory, Memory}, 
+    types::{job_run::JobRunLog, scheduler::JobId}
+};
+
+const MAX_JOB_RUNS: usize = 50;
+
+pub struct JobRunStorage;
+
+thread_local! {
+    static JOB_RUNS: RefCell<BTreeMap<JobId, JobRunLog, Memory>> = RefCell::new(
+        BTreeMap::init(
+            get_job_runs_memory()
+        )
+    );
+}
+
+impl JobRunStorage {
+    pub fn push(
+        id: JobId,
+        run: JobRun
+    ) {
+        JOB_RUNS.with_borrow_mut(|runs| {
+            let mut log = runs.get(&id).unwrap_or_default();
+            log.runs.insert(0, run);
+            log.runs.truncate(MAX_JOB_RUNS);
+            runs.insert(id, log);
+        });
+    }
+
+    pub fn list(
+        id: JobId,
+        offset: usize,
+        size: usize
+    ) -> (Vec<JobRun>, usize) {
+        JOB_RUNS.with_borrow(|runs| {
+            let log = runs.get(&id).unwrap_or_default();
+            (
+                log.runs.iter()
+                    .skip(offset)
+                    .take(size)
+                    .cloned()
+                    .collect::<Vec<_>>(),
+                log.runs.len()
+            )
+        })
+    }
+
+    pub fn remove(
+        id: JobId
+    ) {
+        JOB_RUNS.with_borrow_mut(|runs| {
+            runs.remove(&id);
+        });
+    }
+}
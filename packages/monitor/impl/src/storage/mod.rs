@@ -1 +1,2 @@
-pub mod job;
\ No newline at end of file
+pub mod job;
+pub mod outbox;
\ No newline at end of file
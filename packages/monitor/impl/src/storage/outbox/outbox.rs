@@ -0,0 +1,133 @@
+use std::{cell::RefCell, ops::Bound};
+use ic_stable_structures::BTreeMap;
+use crate::{
+    memory::{get_dead_letters_memory, get_outbox_memory, Memory}, 
+    types::outbox::{OutboxItem, OutboxItemId}
+};
+
+pub struct OutboxStorage;
+
+thread_local! {
+    static OUTBOX: RefCell<BTreeMap<OutboxItemId, OutboxItem, Memory>> = RefCell::new(
+        BTreeMap::init(
+            get_outbox_memory()
+        )
+    );
+
+    static DEAD_LETTERS: RefCell<BTreeMap<OutboxItemId, OutboxItem, Memory>> = RefCell::new(
+        BTreeMap::init(
+            get_dead_letters_memory()
+        )
+    );
+}
+
+impl OutboxStorage {
+    pub fn push(
+        item: OutboxItem
+    ) -> OutboxItemId {
+        // ids keep growing, even when items are moved to the dead letters
+        let last_id = OUTBOX.with_borrow(|outbox| outbox.last_key_value().map(|(id, _)| id))
+            .max(DEAD_LETTERS.with_borrow(|dead| dead.last_key_value().map(|(id, _)| id)));
+        let id = last_id.map(|id| id + 1).unwrap_or(0);
+
+        OUTBOX.with_borrow_mut(|outbox| {
+            outbox.insert(id, item)
+        });
+
+        id
+    }
+
+    pub fn next(
+        after: Option<OutboxItemId>
+    ) -> Option<(OutboxItemId, OutboxItem)> {
+        OUTBOX.with_borrow(|outbox| {
+            match after {
+                Some(id) => outbox.range((Bound::Excluded(id), Bound::Unbounded)).next(),
+                None => outbox.first_key_value(),
+            }
+        })
+    }
+
+    pub fn save(
+        id: OutboxItemId,
+        item: OutboxItem
+    ) {
+        OUTBOX.with_borrow_mut(|outbox| {
+            outbox.insert(id, item)
+        });
+    }
+
+    pub fn remove(
+        id: OutboxItemId
+    ) -> Option<OutboxItem> {
+        OUTBOX.with_borrow_mut(|outbox| {
+            outbox.remove(&id)
+        })
+    }
+
+    pub fn count(
+    ) -> u64 {
+        OUTBOX.with_borrow(|outbox| {
+            outbox.len()
+        })
+    }
+
+    pub fn list(
+        offset: usize,
+        size: usize
+    ) -> Vec<(OutboxItemId, OutboxItem)> {
+        OUTBOX.with_borrow(|outbox| {
+            outbox.iter()
+                .skip(offset)
+                .take(size)
+                .collect::<Vec<_>>()
+        })
+    }
+
+    pub fn ids(
+    ) -> Vec<OutboxItemId> {
+        OUTBOX.with_borrow(|outbox| {
+            outbox.iter()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>()
+        })
+    }
+
+    pub fn save_dead(
+        id: OutboxItemId,
+        item: OutboxItem
+    ) {
+        DEAD_LETTERS.with_borrow_mut(|dead| {
+            dead.insert(id, item)
+        });
+    }
+
+    pub fn remove_dead(
+        id: OutboxItemId
+    ) -> Option<OutboxItem> {
+        DEAD_LETTERS.with_borrow_mut(|dead| {
+            dead.remove(&id)
+        })
+    }
+
+    pub fn list_dead(
+        offset: usize,
+        size: usize
+    ) -> Vec<(OutboxItemId, OutboxItem)> {
+        DEAD_LETTERS.with_borrow(|dead| {
+            dead.iter()
+                .skip(offset)
+                .take(size)
+                .collect::<Vec<_>>()
+        })
+    }
+
+    pub fn dead_ids(
+    ) -> Vec<OutboxItemId> {
+        DEAD_LETTERS.with_borrow(|dead| {
+            dead.iter()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>()
+        })
+    }
+}
@@ -0,0 +1,25 @@
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    Path(Vec<String>),
+    Str(String),
+    Num(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug)]
+pub enum Condition {
+    Value(Operand),
+    Cmp(Operand, CmpOp, Operand),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
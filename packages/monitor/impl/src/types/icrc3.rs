@@ -0,0 +1,30 @@
+use candid::{define_function, CandidType, Nat};
+use icrc_ledger_types::icrc::generic_value::Value;
+use serde::Deserialize;
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct GetBlocksArgs {
+    pub start: Nat,
+    pub length: Nat,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct BlockWithId {
+    pub id: Nat,
+    pub block: Value,
+}
+
+define_function!(pub ArchiveFn : (Vec<GetBlocksArgs>) -> (GetBlocksResult) query);
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ArchivedBlocks {
+    pub args: Vec<GetBlocksArgs>,
+    pub callback: ArchiveFn,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct GetBlocksResult {
+    pub log_length: Nat,
+    pub blocks: Vec<BlockWithId>,
+    pub archived_blocks: Vec<ArchivedBlocks>,
+}
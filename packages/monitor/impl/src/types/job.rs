@@ -1,38 +1,134 @@
 use std::borrow::Cow;
-use candid::{CandidType, Decode, Encode, Principal};
+use candid::{CandidType, Decode, Encode};
 use ic_stable_structures::{storable::Bound, Storable};
-use monitor_api::types::job::{JobCanister, JobState, JobType};
+use monitor_api::{
+    types::job::{JobDelivery, JobDestination, JobState, JobType}, 
+    updates::add_job::AddJobArgs
+};
 use serde::{Deserialize, Serialize};
+use super::proposal::ProposalsCursor;
+
+pub const DEFAULT_MAX_FAILURES: u32 = 10;
 
 #[derive(Clone, Serialize, Deserialize, CandidType)]
 pub struct Job {
     pub ty: JobType,
     pub output_template: String,
+    pub filter: Option<String>,
     pub interval: u32,
     pub batch_size: u32,
     pub state: JobState,
+    // deprecated: superseded by offset64, never read. Still written (capped) for a downgrade
     pub offset: u32,
+    pub proposals: Option<ProposalsCursor>,
+    pub last_run_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub failures: Option<u32>,
+    pub max_failures: Option<u32>,
+    pub destination: Option<JobDestination>,
+    pub next_event: Option<u64>,
+    pub delivery: Option<JobDelivery>,
+    pub offset64: Option<u64>,
 }
 
 impl Job {
-    pub fn canister(
-        canister_id: Principal, 
-        method_name: String, 
-        interval: u32,
-        batch_size: u32,
-        output_template: String, 
-        offset: u32
+    pub fn new(
+        args: AddJobArgs,
+        offset: u64
     ) -> Self {
-        Self {
-            ty: JobType::Canister(JobCanister{
-                canister_id,
-                method_name,
-            }),
-            interval,
-            batch_size,
-            output_template,
+        // proposal jobs track the last proposal id seen, the others an offset
+        let (offset, proposals) = match &args.ty {
+            JobType::SnsProposals(_) | JobType::NnsProposals(_) => {
+                (0, Some(ProposalsCursor {
+                    last_id: offset,
+                    ..Default::default()
+                }))
+            },
+            _ => {
+                (offset, None)
+            }
+        };
+
+        let mut job = Self {
+            ty: args.ty,
+            interval: args.interval,
+            batch_size: args.batch_size,
+            output_template: args.output_template,
+            filter: args.filter,
             state: JobState::Running,
-            offset
+            offset: 0,
+            proposals,
+            last_run_at: None,
+            last_error: None,
+            failures: None,
+            max_failures: args.max_failures,
+            destination: args.destination,
+            next_event: None,
+            delivery: args.delivery,
+            offset64: None,
+        };
+        job.set_offset(offset);
+        job
+    }
+
+    // always set: by new() or, for the jobs saved before it, by from_bytes()
+    pub fn offset(
+        &self
+    ) -> u64 {
+        self.offset64.unwrap_or_default()
+    }
+
+    pub fn set_offset(
+        &mut self,
+        offset: u64
+    ) {
+        self.offset64 = Some(offset);
+        self.offset = u32::try_from(offset).unwrap_or(u32::MAX);
+    }
+
+    // 0 disables the automatic suspension
+    pub fn max_failures(
+        &self
+    ) -> u32 {
+        self.max_failures.unwrap_or(DEFAULT_MAX_FAILURES)
+    }
+
+    pub fn delivery(
+        &self
+    ) -> JobDelivery {
+        self.delivery.clone().unwrap_or(JobDelivery::Immediate)
+    }
+
+    // the index of the next event to be posted
+    pub fn next_event_index(
+        &mut self
+    ) -> u64 {
+        let index = self.next_event.unwrap_or(0);
+        self.next_event = Some(index + 1);
+        index
+    }
+
+    pub fn position(
+        &self
+    ) -> u64 {
+        match &self.proposals {
+            Some(cursor) => cursor.last_id,
+            None => self.offset(),
+        }
+    }
+
+    pub fn set_position(
+        &mut self,
+        position: u64
+    ) {
+        match &mut self.proposals {
+            Some(cursor) => {
+                cursor.last_id = position;
+                cursor.pending.retain(|id, _| *id <= position);
+            },
+            None => {
+                self.set_offset(position);
+            }
         }
     }
 }
@@ -47,7 +143,11 @@ impl Storable for Job {
     fn from_bytes(
         bytes: Cow<[u8]>
     ) -> Self {
-        Decode!(bytes.as_ref(), Self).unwrap()
+        let mut job = Decode!(bytes.as_ref(), Self).unwrap();
+        if job.offset64.is_none() {
+            job.offset64 = Some(job.offset as u64);
+        }
+        job
     }
 
     const BOUND: Bound = Bound::Unbounded;
@@ -0,0 +1,28 @@
+use std::borrow::Cow;
+use bot_api::updates::notify_events::JobEvent;
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+// events waiting to be posted together
+#[derive(Clone, Default, Serialize, Deserialize, CandidType)]
+pub struct JobDigest {
+    pub events: Vec<JobEvent>,
+    pub since: u64,
+}
+
+impl Storable for JobDigest {
+    fn to_bytes(
+        &self
+    ) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(
+        bytes: Cow<[u8]>
+    ) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -0,0 +1,27 @@
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use monitor_api::queries::get_job_runs::JobRun;
+use serde::{Deserialize, Serialize};
+
+// newest first
+#[derive(Clone, Default, Serialize, Deserialize, CandidType)]
+pub struct JobRunLog {
+    pub runs: Vec<JobRun>,
+}
+
+impl Storable for JobRunLog {
+    fn to_bytes(
+        &self
+    ) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(
+        bytes: Cow<[u8]>
+    ) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -1,3 +1,12 @@
 pub mod job;
 pub mod active_job;
 pub mod scheduler;
+pub mod template;
+pub mod icrc3;
+pub mod proposal;
+pub mod sns;
+pub mod nns;
+pub mod condition;
+pub mod outbox;
+pub mod job_run;
+pub mod job_digest;
\ No newline at end of file
@@ -0,0 +1,57 @@
+use candid::CandidType;
+use serde::Deserialize;
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ProposalId {
+    pub id: u64,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct NeuronId {
+    pub id: u64,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct Tally {
+    pub yes: u64,
+    pub no: u64,
+    pub total: u64,
+    pub timestamp_seconds: u64,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct Proposal {
+    pub title: Option<String>,
+    pub summary: String,
+    pub url: String,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ProposalInfo {
+    pub id: Option<ProposalId>,
+    pub status: i32,
+    pub topic: i32,
+    pub proposal: Option<Proposal>,
+    pub proposer: Option<NeuronId>,
+    pub latest_tally: Option<Tally>,
+    pub proposal_timestamp_seconds: u64,
+    pub decided_timestamp_seconds: u64,
+    pub executed_timestamp_seconds: u64,
+    pub failed_timestamp_seconds: u64,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ListProposalInfo {
+    pub include_reward_status: Vec<i32>,
+    pub omit_large_fields: Option<bool>,
+    pub before_proposal: Option<ProposalId>,
+    pub limit: u32,
+    pub exclude_topic: Vec<i32>,
+    pub include_all_manage_neuron_proposals: Option<bool>,
+    pub include_status: Vec<i32>,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ListProposalInfoResponse {
+    pub proposal_info: Vec<ProposalInfo>,
+}
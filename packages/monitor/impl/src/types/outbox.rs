@@ -0,0 +1,70 @@
+use std::borrow::Cow;
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::{storable::Bound, Storable};
+use bot_api::updates::notify_events::JobEvent;
+use monitor_api::types::job::JobDestination;
+use serde::{Deserialize, Serialize};
+use super::scheduler::JobId;
+
+pub type OutboxItemId = u64;
+
+#[derive(Clone, Serialize, Deserialize, CandidType)]
+pub struct OutboxItem {
+    pub job_id: JobId,
+    pub events: Vec<JobEvent>,
+    // rendered around the events of a digest
+    pub header: Option<String>,
+    pub footer: Option<String>,
+    pub destination: Option<JobDestination>,
+    pub created_at: u64,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+}
+
+impl OutboxItem {
+    pub fn new(
+        job_id: JobId,
+        events: Vec<JobEvent>,
+        destination: Option<JobDestination>,
+        now: u64
+    ) -> Self {
+        Self {
+            job_id,
+            events,
+            header: None,
+            footer: None,
+            destination,
+            created_at: now,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+        }
+    }
+}
+
+impl Storable for OutboxItem {
+    fn to_bytes(
+        &self
+    ) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(
+        bytes: Cow<[u8]>
+    ) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl OutboxItem {
+    pub fn texts(
+        &self
+    ) -> Vec<String> {
+        self.events.iter()
+            .map(|e| e.text.clone())
+            .collect()
+    }
+}
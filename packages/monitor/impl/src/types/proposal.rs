@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, CandidType)]
+pub enum ProposalStage {
+    Open,
+    Adopted,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, CandidType)]
+pub struct ProposalsCursor {
+    pub last_id: u64,
+    pub pending: BTreeMap<u64, ProposalStage>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProposalStatus {
+    Open,
+    Rejected,
+    Adopted,
+    Executed,
+    Failed,
+}
+
+#[derive(Clone)]
+pub struct ProposalSnapshot {
+    pub id: u64,
+    pub status: ProposalStatus,
+    pub title: String,
+    pub summary: String,
+    pub url: String,
+    pub proposer: Option<String>,
+    pub topic: Option<String>,
+    pub action: Option<String>,
+    pub yes: u64,
+    pub no: u64,
+    pub total: u64,
+    pub created_at: u64,
+    pub decided_at: u64,
+    pub executed_at: u64,
+    pub failed_at: u64,
+}
@@ -0,0 +1,84 @@
+use candid::CandidType;
+use serde::Deserialize;
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ProposalId {
+    pub id: u64,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct NeuronId {
+    pub id: Vec<u8>,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct Tally {
+    pub yes: u64,
+    pub no: u64,
+    pub total: u64,
+    pub timestamp_seconds: u64,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct Percentage {
+    pub basis_points: Option<u64>,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct Proposal {
+    pub title: String,
+    pub summary: String,
+    pub url: String,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ProposalData {
+    pub id: Option<ProposalId>,
+    pub action: u64,
+    pub proposal: Option<Proposal>,
+    pub proposer: Option<NeuronId>,
+    pub latest_tally: Option<Tally>,
+    pub proposal_creation_timestamp_seconds: u64,
+    pub decided_timestamp_seconds: u64,
+    pub executed_timestamp_seconds: u64,
+    pub failed_timestamp_seconds: u64,
+    pub minimum_yes_proportion_of_total: Option<Percentage>,
+    pub minimum_yes_proportion_of_exercised: Option<Percentage>,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ListProposals {
+    pub exclude_type: Vec<u64>,
+    pub before_proposal: Option<ProposalId>,
+    pub limit: u32,
+    pub include_reward_status: Vec<i32>,
+    pub include_status: Vec<i32>,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ListProposalsResponse {
+    pub proposals: Vec<ProposalData>,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct GetProposal {
+    pub proposal_id: Option<ProposalId>,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct GovernanceError {
+    pub error_type: i32,
+    pub error_message: String,
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub enum GetProposalResult {
+    Error(GovernanceError),
+    Proposal(ProposalData),
+}
+
+#[derive(Clone, CandidType, Deserialize)]
+pub struct GetProposalResponse {
+    pub result: Option<GetProposalResult>,
+}
+
@@ -0,0 +1,38 @@
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Str(String),
+    Int(i64),
+}
+
+#[derive(Clone, Debug)]
+pub struct Filter {
+    pub name: String,
+    pub args: Vec<Literal>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Expr {
+    pub path: Vec<String>,
+    pub filters: Vec<Filter>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Node {
+    Text(String),
+    Expr(Expr),
+    If {
+        cond: Expr,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        expr: Expr,
+        alias: String,
+        body: Vec<Node>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Template {
+    pub nodes: Vec<Node>,
+}
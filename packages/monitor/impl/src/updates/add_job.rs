@@ -1,27 +1,30 @@
 use monitor_api::updates::add_job::{AddJobArgs, AddJobResult};
-use crate::{guards::*, services::manager::manager::JobManager, types::job::Job};
+use crate::{
+    guards::*, 
+    services::manager::manager::JobManager, 
+    types::{condition::Condition, job::Job, template::Template}, 
+    utils::delivery::validate_delivery
+};
 
 #[ic_cdk::update(guard = "owner_only")]
 pub async fn add_job(
     args: AddJobArgs
 ) -> AddJobResult {
-    let offset = if args.offset > 0 {
-        args.offset
+    Template::parse(&args.output_template)?;
+    if let Some(filter) = &args.filter {
+        Condition::parse(filter)?;
     }
-    else {
-        JobManager::get_current_offset(
-            &args.canister_id, &args.method_name
-        ).await?
+    if let Some(delivery) = &args.delivery {
+        validate_delivery(delivery)?;
+    }
+
+    // no offset means starting from the source's current head
+    let offset = match args.offset {
+        Some(offset) => offset,
+        None => JobManager::get_current_offset(&args.ty).await?,
     };
 
-    let job = Job::canister(
-        args.canister_id,
-        args.method_name,
-        args.interval,
-        args.batch_size,
-        args.output_template,
-        offset
-    );
+    let job = Job::new(args, offset);
 
     match JobManager::add(job) {
         Ok(job_id) =>  {
@@ -0,0 +1,17 @@
+use monitor_api::updates::drop_outbox::{DropOutboxArgs, DropOutboxResult};
+use crate::{guards::*, services::outbox::outbox::OutboxService};
+
+#[ic_cdk::update(guard = "owner_only")]
+pub fn drop_outbox(
+    args: DropOutboxArgs
+) -> DropOutboxResult {
+    match OutboxService::drop(args.id, args.dead) {
+        Ok(count) =>  {
+            Ok(count)
+        }
+        Err(err) => {
+            ic_cdk::println!("error: {}", err);
+            Err(err)
+        }
+    }
+}
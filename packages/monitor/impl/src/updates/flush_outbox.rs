@@ -0,0 +1,10 @@
+use monitor_api::updates::flush_outbox::FlushOutboxResult;
+use crate::{guards::*, services::outbox::outbox::OutboxService};
+
+#[ic_cdk::update(guard = "owner_only")]
+pub fn flush_outbox(
+) -> FlushOutboxResult {
+    Ok(
+        OutboxService::flush()
+    )
+}
@@ -1,4 +1,8 @@
 pub mod add_job;
 pub mod del_job;
 pub mod start_job;
-pub mod stop_job;
\ No newline at end of file
+pub mod stop_job;
+pub mod update_job;
+pub mod seek_job;
+pub mod flush_outbox;
+pub mod drop_outbox;pub mod withdraw_cycles;
@@ -0,0 +1,17 @@
+use monitor_api::updates::seek_job::{SeekJobArgs, SeekJobResult};
+use crate::{guards::*, services::manager::manager::JobManager};
+
+#[ic_cdk::update(guard = "owner_only")]
+pub async fn seek_job(
+    args: SeekJobArgs
+) -> SeekJobResult {
+    match JobManager::seek(args.job_id, args.position).await {
+        Ok(offset) =>  {
+            Ok(offset)
+        }
+        Err(err) => {
+            ic_cdk::println!("error: {}", err);
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use monitor_api::updates::update_job::{UpdateJobArgs, UpdateJobResult};
+use crate::{
+    guards::*, 
+    services::manager::manager::JobManager, 
+    types::{condition::Condition, template::Template}, 
+    utils::delivery::validate_delivery
+};
+
+#[ic_cdk::update(guard = "owner_only")]
+pub fn update_job(
+    args: UpdateJobArgs
+) -> UpdateJobResult {
+    if let Some(output_template) = &args.output_template {
+        Template::parse(output_template)?;
+    }
+    if let Some(filter) = &args.filter {
+        if !filter.is_empty() {
+            Condition::parse(filter)?;
+        }
+    }
+    if let Some(delivery) = &args.delivery {
+        validate_delivery(delivery)?;
+    }
+
+    match JobManager::update(args) {
+        Ok(()) =>  {
+            Ok(())
+        }
+        Err(err) => {
+            ic_cdk::println!("error: {}", err);
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use ic_cdk::api::management_canister::main::{deposit_cycles, CanisterIdRecord};
+use monitor_api::updates::withdraw_cycles::WithdrawCyclesResult;
+use crate::{guards::*, state};
+
+// kept to pay for the deposit call itself and to stay above the freezing threshold
+const RESERVED_CYCLES: u128 = 20_000_000_000;
+
+#[ic_cdk::update(guard = "owner_only")]
+pub async fn withdraw_cycles(
+) -> WithdrawCyclesResult {
+    let bot_canister_id = state::read(|s| *s.bot_canister_id());
+
+    let cycles = ic_cdk::api::canister_balance128().saturating_sub(RESERVED_CYCLES);
+    if cycles == 0 {
+        return Ok(0);
+    }
+
+    match deposit_cycles(
+        CanisterIdRecord { canister_id: bot_canister_id }, 
+        cycles
+    ).await {
+        Ok(_) => {
+            Ok(cycles)
+        },
+        Err(err) => {
+            let err = format!("depositing cycles to canister {}: {}", bot_canister_id, err.1);
+            ic_cdk::println!("error: {}", err);
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use std::{cmp::Ordering, collections::BTreeMap, iter::Peekable, vec::IntoIter};
+use icrc_ledger_types::icrc::generic_value::Value;
+use crate::{
+    types::condition::{CmpOp, Condition, Operand},
+    utils::filters::{is_integer, is_truthy, to_text}
+};
+
+#[derive(PartialEq)]
+enum Lexeme {
+    Ident(String),
+    Str(String),
+    Num(String),
+    Cmp(CmpOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+type Lexemes = Peekable<IntoIter<(Lexeme, usize)>>;
+
+impl Condition {
+    // grammar: or := and ('||' and)*, and := unary ('&&' unary)*, 
+    // unary := '!' unary | '(' or ')' | operand [cmp operand]
+    pub fn parse(
+        src: &str
+    ) -> Result<Self, String> {
+        let mut lexemes = Self::lex(src)?.into_iter().peekable();
+
+        let cond = Self::parse_or(&mut lexemes)?;
+        if let Some((_, pos)) = lexemes.next() {
+            return Err(format!("Filter error at position {}: unexpected input", pos));
+        }
+
+        Ok(cond)
+    }
+
+    pub fn eval(
+        &self,
+        event: &BTreeMap<String, Value>
+    ) -> bool {
+        match self {
+            Condition::Value(operand) => {
+                is_truthy(&Self::resolve(operand, event))
+            },
+            Condition::Cmp(left, op, right) => {
+                Self::compare(
+                    Self::resolve(left, event), 
+                    *op, 
+                    Self::resolve(right, event)
+                )
+            },
+            Condition::Not(cond) => {
+                !cond.eval(event)
+            },
+            Condition::And(left, right) => {
+                left.eval(event) && right.eval(event)
+            },
+            Condition::Or(left, right) => {
+                left.eval(event) || right.eval(event)
+            },
+        }
+    }
+
+    fn parse_or(
+        lexemes: &mut Lexemes
+    ) -> Result<Self, String> {
+        let mut cond = Self::parse_and(lexemes)?;
+        while lexemes.next_if(|(l, _)| *l == Lexeme::Or).is_some() {
+            cond = Condition::Or(Box::new(cond), Box::new(Self::parse_and(lexemes)?));
+        }
+        Ok(cond)
+    }
+
+    fn parse_and(
+        lexemes: &mut Lexemes
+    ) -> Result<Self, String> {
+        let mut cond = Self::parse_unary(lexemes)?;
+        while lexemes.next_if(|(l, _)| *l == Lexeme::And).is_some() {
+            cond = Condition::And(Box::new(cond), Box::new(Self::parse_unary(lexemes)?));
+        }
+        Ok(cond)
+    }
+
+    fn parse_unary(
+        lexemes: &mut Lexemes
+    ) -> Result<Self, String> {
+        match lexemes.next() {
+            Some((Lexeme::Not, _)) => {
+                Ok(Condition::Not(Box::new(Self::parse_unary(lexemes)?)))
+            },
+            Some((Lexeme::LParen, pos)) => {
+                let cond = Self::parse_or(lexemes)?;
+                match lexemes.next() {
+                    Some((Lexeme::RParen, _)) => Ok(cond),
+                    _ => Err(format!("Filter error at position {}: '(' without ')'", pos)),
+                }
+            },
+            Some((lexeme, pos)) => {
+                let left = Self::to_operand(lexeme, pos)?;
+                match lexemes.next_if(|(l, _)| matches!(l, Lexeme::Cmp(_))) {
+                    Some((Lexeme::Cmp(op), pos)) => {
+                        let right = match lexemes.next() {
+                            Some((lexeme, pos)) => Self::to_operand(lexeme, pos)?,
+                            None => return Err(format!("Filter error at position {}: expected a value after the operator", pos)),
+                        };
+                        Ok(Condition::Cmp(left, op, right))
+                    },
+                    _ => {
+                        Ok(Condition::Value(left))
+                    }
+                }
+            },
+            None => {
+                Err("Filter error: unexpected end of expression".to_string())
+            }
+        }
+    }
+
+    fn to_operand(
+        lexeme: Lexeme,
+        pos: usize
+    ) -> Result<Operand, String> {
+        match lexeme {
+            Lexeme::Ident(ident) => {
+                let path = ident.split('.')
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>();
+                if path.iter().any(|s| s.is_empty()) {
+                    return Err(format!("Filter error at position {}: invalid field path '{}'", pos, ident));
+                }
+                Ok(Operand::Path(path))
+            },
+            Lexeme::Str(s) => {
+                Ok(Operand::Str(s))
+            },
+            Lexeme::Num(n) => {
+                Ok(Operand::Num(n))
+            },
+            _ => {
+                Err(format!("Filter error at position {}: expected a field name, a string or a number", pos))
+            }
+        }
+    }
+
+    fn lex(
+        src: &str
+    ) -> Result<Vec<(Lexeme, usize)>, String> {
+        let chars = src.chars().collect::<Vec<_>>();
+        let mut lexemes = vec![];
+        let mut i = 0;
+
+        while i < chars.len() {
+            let pos = i;
+            let next = chars.get(i + 1).copied();
+            let lexeme = match (chars[i], next) {
+                (c, _) if c.is_whitespace() => {
+                    i += 1;
+                    continue;
+                },
+                ('&', Some('&')) => Lexeme::And,
+                ('|', Some('|')) => Lexeme::Or,
+                ('=', Some('=')) => Lexeme::Cmp(CmpOp::Eq),
+                ('!', Some('=')) => Lexeme::Cmp(CmpOp::Ne),
+                ('<', Some('=')) => Lexeme::Cmp(CmpOp::Le),
+                ('>', Some('=')) => Lexeme::Cmp(CmpOp::Ge),
+                ('<', _) => Lexeme::Cmp(CmpOp::Lt),
+                ('>', _) => Lexeme::Cmp(CmpOp::Gt),
+                ('!', _) => Lexeme::Not,
+                ('(', _) => Lexeme::LParen,
+                (')', _) => Lexeme::RParen,
+                ('"', _) => {
+                    let mut s = String::new();
+                    let mut closed = false;
+                    i += 1;
+                    while i < chars.len() {
+                        let c = chars[i];
+                        i += 1;
+                        match c {
+                            '"' => {
+                                closed = true;
+                                break;
+                            },
+                            '\\' if i < chars.len() => {
+                                s.push(chars[i]);
+                                i += 1;
+                            },
+                            c => s.push(c),
+                        }
+                    }
+                    if !closed {
+                        return Err(format!("Filter error at position {}: unterminated string", pos));
+                    }
+                    lexemes.push((Lexeme::Str(s), pos));
+                    continue;
+                },
+                (c, next) if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                    i += 1;
+                    while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '_') {
+                        i += 1;
+                    }
+                    let num = chars[pos..i].iter()
+                        .filter(|c| **c != '_')
+                        .collect::<String>();
+                    lexemes.push((Lexeme::Num(num), pos));
+                    continue;
+                },
+                (c, _) if c.is_alphanumeric() || c == '_' => {
+                    while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                        i += 1;
+                    }
+                    lexemes.push((Lexeme::Ident(chars[pos..i].iter().collect()), pos));
+                    continue;
+                },
+                (c, _) => {
+                    return Err(format!("Filter error at position {}: unexpected character '{}'", pos, c));
+                }
+            };
+
+            i += match lexeme {
+                Lexeme::And | Lexeme::Or | Lexeme::Cmp(CmpOp::Eq | CmpOp::Ne | CmpOp::Le | CmpOp::Ge) => 2,
+                _ => 1,
+            };
+            lexemes.push((lexeme, pos));
+        }
+
+        Ok(lexemes)
+    }
+
+    fn resolve(
+        operand: &Operand,
+        event: &BTreeMap<String, Value>
+    ) -> Option<Value> {
+        match operand {
+            Operand::Str(s) => Some(Value::Text(s.clone())),
+            Operand::Num(n) => Some(Value::Text(n.clone())),
+            Operand::Path(path) => {
+                let (head, tail) = path.split_first()?;
+                let mut value = event.get(head)?;
+                for key in tail {
+                    value = match value {
+                        Value::Map(map) => map.get(key)?,
+                        Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+                        _ => return None,
+                    };
+                }
+                Some(value.clone())
+            },
+        }
+    }
+
+    fn compare(
+        left: Option<Value>,
+        op: CmpOp,
+        right: Option<Value>
+    ) -> bool {
+        let (left, right) = match (left, right) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return op == CmpOp::Ne,
+        };
+
+        let ord = match (Self::to_number(&left), Self::to_number(&right)) {
+            (Some(left), Some(right)) => Self::compare_numbers(&left, &right),
+            _ => to_text(&left).cmp(&to_text(&right)),
+        };
+
+        match op {
+            CmpOp::Eq => ord == Ordering::Equal,
+            CmpOp::Ne => ord != Ordering::Equal,
+            CmpOp::Lt => ord == Ordering::Less,
+            CmpOp::Le => ord != Ordering::Greater,
+            CmpOp::Gt => ord == Ordering::Greater,
+            CmpOp::Ge => ord != Ordering::Less,
+        }
+    }
+
+    fn to_number(
+        value: &Value
+    ) -> Option<String> {
+        match value {
+            Value::Nat(n) => Some(n.0.to_string()),
+            Value::Nat64(n) => Some(n.to_string()),
+            Value::Int(i) => Some(i.0.to_string()),
+            Value::Text(text) if is_integer(text) => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    // compares integers of any size in decimal format
+    fn compare_numbers(
+        left: &str,
+        right: &str
+    ) -> Ordering {
+        let split = |n: &str| {
+            let (neg, digits) = match n.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, n),
+            };
+            let digits = digits.trim_start_matches('0').to_string();
+            (neg && !digits.is_empty(), digits)
+        };
+
+        let (left_neg, left) = split(left);
+        let (right_neg, right) = split(right);
+
+        match (left_neg, right_neg) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (neg, _) => {
+                let ord = left.len().cmp(&right.len())
+                    .then_with(|| left.cmp(&right));
+                if neg {
+                    ord.reverse()
+                }
+                else {
+                    ord
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::{Int, Nat};
+    use super::*;
+
+    fn event(
+    ) -> BTreeMap<String, Value> {
+        BTreeMap::from([
+            ("kind".to_string(), Value::Text("transfer".to_string())),
+            ("amount".to_string(), Value::Nat(Nat::from(1_000u32))),
+            ("big".to_string(), Value::Nat(Nat::parse(b"123456789012345678901234567890").unwrap())),
+            ("delta".to_string(), Value::Int(Int::from(-5))),
+            ("zero".to_string(), Value::Nat64(0)),
+            ("tx".to_string(), Value::Map(BTreeMap::from([
+                ("memo".to_string(), Value::Text("hi".to_string())),
+            ]))),
+        ])
+    }
+
+    fn eval(
+        src: &str
+    ) -> bool {
+        Condition::parse(src).unwrap().eval(&event())
+    }
+
+    #[test]
+    fn compares_values() {
+        assert!(eval("kind == \"transfer\""));
+        assert!(eval("kind != \"mint\""));
+        assert!(eval("amount >= 1000 && amount < 1001"));
+        assert!(eval("amount > 999"));
+        assert!(eval("big > 123456789012345678901234567889"));
+        assert!(eval("delta < -4 && delta > -6"));
+        assert!(eval("tx.memo == \"hi\""));
+        assert!(eval("missing != 1"));
+        assert!(!eval("missing == 1"));
+    }
+
+    #[test]
+    fn evaluates_truthiness() {
+        assert!(eval("kind"));
+        assert!(!eval("zero"));
+        assert!(!eval("missing"));
+        assert!(eval("!missing"));
+        assert!(eval("!!kind"));
+    }
+
+    #[test]
+    fn respects_precedence() {
+        // && binds tighter than ||
+        assert!(eval("kind == \"transfer\" || zero && missing"));
+        assert!(!eval("(kind == \"transfer\" || zero) && missing"));
+        assert!(eval("missing && zero || kind"));
+        // ! binds tighter than &&
+        assert!(!eval("!kind && kind"));
+        assert!(eval("!(kind && zero)"));
+
+        match Condition::parse("a || b && c").unwrap() {
+            Condition::Or(left, right) => {
+                assert!(matches!(*left, Condition::Value(_)));
+                assert!(matches!(*right, Condition::And(_, _)));
+            },
+            other => {
+                panic!("unexpected {:?}", other);
+            }
+        }
+
+        // left-associative
+        match Condition::parse("a && b && c").unwrap() {
+            Condition::And(left, right) => {
+                assert!(matches!(*left, Condition::And(_, _)));
+                assert!(matches!(*right, Condition::Value(_)));
+            },
+            other => {
+                panic!("unexpected {:?}", other);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_conditions() {
+        let cases = [
+            ("", "unexpected end of expression"),
+            ("kind ==", "expected a value after the operator"),
+            ("(kind", "position 0: '(' without ')'"),
+            ("kind)", "position 4: unexpected input"),
+            ("kind == \"x", "position 8: unterminated string"),
+            ("kind # 1", "position 5: unexpected character '#'"),
+            ("a..b", "invalid field path 'a..b'"),
+            ("kind && || 1", "expected a field name, a string or a number"),
+            ("kind == == 1", "expected a field name, a string or a number"),
+        ];
+
+        for (src, err) in cases {
+            match Condition::parse(src) {
+                Ok(_) => panic!("'{}' should fail", src),
+                Err(e) => assert!(e.contains(err), "'{}': '{}' doesn't contain '{}'", src, e, err),
+            }
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use monitor_api::types::job::JobDelivery;
+use crate::types::template::Template;
+
+// pending events are posted once there are this many of them, whatever the delivery mode
+pub const MAX_DIGEST_EVENTS: usize = 100;
+
+pub fn validate_delivery(
+    delivery: &JobDelivery
+) -> Result<(), String> {
+    match delivery {
+        JobDelivery::Immediate => {},
+        JobDelivery::Digest { every, header, footer } => {
+            if *every == 0 {
+                return Err("The digest period must be at least 1 minute".to_string());
+            }
+            if let Some(header) = header {
+                Template::parse(header)?;
+            }
+            if let Some(footer) = footer {
+                Template::parse(footer)?;
+            }
+        },
+        JobDelivery::Threshold { events } => {
+            if *events == 0 || *events as usize > MAX_DIGEST_EVENTS {
+                return Err(format!("The threshold must be between 1 and {} events", MAX_DIGEST_EVENTS));
+            }
+        },
+    }
+
+    Ok(())
+}
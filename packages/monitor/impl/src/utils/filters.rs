@@ -0,0 +1,325 @@
+use candid::{Int, Nat};
+use icrc_ledger_types::icrc::generic_value::Value;
+use crate::{
+    types::template::{Filter, Literal}, 
+    utils::format::{format_account, format_datetime, format_principal, format_tokens}
+};
+
+const DEFAULT_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+const FILTERS: &[&str] = &[
+    "upper", "lower", "trim", "length", "first", "last", "truncate", "default", "join",
+    "tokens", "datetime", "account", "hex", "principal",
+];
+
+pub fn validate(
+    filter: &Filter
+) -> Result<(), String> {
+    let valid = match (filter.name.as_str(), filter.args.as_slice()) {
+        ("upper" | "lower" | "trim" | "length" | "first" | "last", []) => true,
+        ("truncate", [Literal::Int(n)]) => *n > 0,
+        ("default", [_]) => true,
+        ("join", [Literal::Str(_)]) => true,
+        ("tokens", [Literal::Int(n)] | [Literal::Int(n), Literal::Str(_)]) => (0..=38).contains(n),
+        ("datetime", [] | [Literal::Str(_)]) => true,
+        ("account" | "hex" | "principal", []) => true,
+        (name, _) if FILTERS.contains(&name) => false,
+        (name, _) => return Err(format!("unknown filter '{}'", name)),
+    };
+
+    if valid {
+        Ok(())
+    }
+    else {
+        Err(format!("invalid arguments for filter '{}'", filter.name))
+    }
+}
+
+pub fn apply(
+    filter: &Filter,
+    value: Option<Value>
+) -> Option<Value> {
+    match (filter.name.as_str(), filter.args.as_slice()) {
+        ("default", [arg]) if is_empty(&value) => {
+            Some(literal_to_value(arg))
+        },
+        ("upper", _) => {
+            Some(Value::Text(to_text(&value?).to_uppercase()))
+        },
+        ("lower", _) => {
+            Some(Value::Text(to_text(&value?).to_lowercase()))
+        },
+        ("trim", _) => {
+            Some(Value::Text(to_text(&value?).trim().to_string()))
+        },
+        ("truncate", [Literal::Int(n)]) => {
+            let text = to_text(&value?);
+            let n = *n as usize;
+            if text.chars().count() > n {
+                Some(Value::Text(format!("{}…", text.chars().take(n).collect::<String>())))
+            }
+            else {
+                Some(Value::Text(text))
+            }
+        },
+        ("length", _) => {
+            let len = match value? {
+                Value::Text(text) => text.chars().count(),
+                Value::Blob(blob) => blob.len(),
+                Value::Array(items) => items.len(),
+                Value::Map(map) => map.len(),
+                other => to_text(&other).chars().count(),
+            };
+            Some(Value::Nat64(len as _))
+        },
+        ("first", _) => {
+            match value? {
+                Value::Array(items) => items.into_iter().next(),
+                other => Some(other),
+            }
+        },
+        ("last", _) => {
+            match value? {
+                Value::Array(items) => items.into_iter().last(),
+                other => Some(other),
+            }
+        },
+        ("join", [Literal::Str(sep)]) => {
+            match value? {
+                Value::Array(items) => Some(Value::Text(
+                    items.iter()
+                        .map(to_text)
+                        .collect::<Vec<_>>()
+                        .join(sep)
+                )),
+                other => Some(other),
+            }
+        },
+        ("tokens", args) => {
+            let amount = match value? {
+                Value::Nat(n) => n.0.to_string(),
+                Value::Nat64(n) => n.to_string(),
+                Value::Int(i) => i.0.to_string(),
+                Value::Text(text) if is_integer(&text) => text,
+                other => return Some(other),
+            };
+            let decimals = match args.first() {
+                Some(Literal::Int(n)) => *n as usize,
+                _ => 0,
+            };
+            let symbol = match args.get(1) {
+                Some(Literal::Str(symbol)) => Some(symbol.as_str()),
+                _ => None,
+            };
+            Some(Value::Text(format_tokens(&amount, decimals, symbol)))
+        },
+        ("datetime", args) => {
+            let timestamp = match value? {
+                Value::Nat(n) => u64::try_from(n.0).ok()?,
+                Value::Nat64(n) => n,
+                Value::Int(i) => u64::try_from(i.0).ok()?,
+                Value::Text(text) => match text.parse::<u64>() {
+                    Ok(n) => n,
+                    Err(_) => return Some(Value::Text(text)),
+                },
+                other => return Some(other),
+            };
+            let fmt = match args.first() {
+                Some(Literal::Str(fmt)) => fmt.as_str(),
+                _ => DEFAULT_DATETIME_FORMAT,
+            };
+            Some(Value::Text(format_datetime(timestamp, fmt)))
+        },
+        ("account", _) => {
+            let value = value?;
+            let text = match &value {
+                Value::Blob(owner) => format_account(owner, None),
+                Value::Array(parts) => match parts.as_slice() {
+                    [Value::Blob(owner)] => format_account(owner, None),
+                    [Value::Blob(owner), Value::Blob(sub)] => format_account(owner, Some(sub)),
+                    _ => None,
+                },
+                Value::Map(map) => match (map.get("owner"), map.get("subaccount")) {
+                    (Some(Value::Blob(owner)), Some(Value::Blob(sub))) => format_account(owner, Some(sub)),
+                    (Some(Value::Blob(owner)), _) => format_account(owner, None),
+                    (Some(Value::Text(owner)), _) => Some(owner.clone()),
+                    _ => None,
+                },
+                _ => None,
+            };
+            Some(text.map(Value::Text).unwrap_or(value))
+        },
+        ("hex", _) => {
+            match value? {
+                Value::Blob(blob) => Some(Value::Text(hex::encode(blob.as_slice()))),
+                Value::Nat(n) => Some(Value::Text(n.0.to_str_radix(16))),
+                Value::Nat64(n) => Some(Value::Text(format!("{:x}", n))),
+                Value::Text(text) => Some(Value::Text(hex::encode(text.as_bytes()))),
+                other => Some(other),
+            }
+        },
+        ("principal", _) => {
+            match value? {
+                Value::Blob(blob) => Some(
+                    format_principal(&blob)
+                        .map(Value::Text)
+                        .unwrap_or(Value::Blob(blob))
+                ),
+                other => Some(other),
+            }
+        },
+        _ => {
+            value
+        }
+    }
+}
+
+pub fn to_text(
+    value: &Value
+) -> String {
+    match value {
+        Value::Text(text) => text.clone(),
+        Value::Nat(n) => n.0.to_string(),
+        Value::Nat64(n) => n.to_string(),
+        Value::Int(i) => i.0.to_string(),
+        Value::Blob(blob) => hex::encode(blob.as_slice()),
+        Value::Array(items) => items.iter()
+            .map(to_text)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Map(map) => map.iter()
+            .map(|(key, value)| format!("{}: {}", key, to_text(value)))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+pub fn is_truthy(
+    value: &Option<Value>
+) -> bool {
+    match value {
+        Some(Value::Nat(n)) => *n != Nat::from(0u32),
+        Some(Value::Nat64(n)) => *n != 0,
+        Some(Value::Int(i)) => *i != Int::from(0),
+        Some(Value::Text(text)) => !text.is_empty() && text != "false",
+        _ => !is_empty(value),
+    }
+}
+
+fn is_empty(
+    value: &Option<Value>
+) -> bool {
+    match value {
+        None => true,
+        Some(Value::Text(text)) => text.is_empty(),
+        Some(Value::Blob(blob)) => blob.is_empty(),
+        Some(Value::Array(items)) => items.is_empty(),
+        Some(Value::Map(map)) => map.is_empty(),
+        Some(_) => false,
+    }
+}
+
+pub fn is_integer(
+    text: &str
+) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn literal_to_value(
+    literal: &Literal
+) -> Value {
+    match literal {
+        Literal::Str(s) => Value::Text(s.clone()),
+        Literal::Int(n) => Value::Int(Int::from(*n)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(
+        name: &str,
+        args: Vec<Literal>
+    ) -> Filter {
+        Filter {
+            name: name.to_string(),
+            args
+        }
+    }
+
+    fn s(
+        text: &str
+    ) -> Literal {
+        Literal::Str(text.to_string())
+    }
+
+    #[test]
+    fn validates_arguments() {
+        let valid = [
+            ("upper", vec![]),
+            ("lower", vec![]),
+            ("trim", vec![]),
+            ("length", vec![]),
+            ("first", vec![]),
+            ("last", vec![]),
+            ("truncate", vec![Literal::Int(10)]),
+            ("default", vec![s("-")]),
+            ("default", vec![Literal::Int(0)]),
+            ("join", vec![s(", ")]),
+            ("tokens", vec![Literal::Int(8)]),
+            ("tokens", vec![Literal::Int(0), s("ICP")]),
+            ("tokens", vec![Literal::Int(38)]),
+            ("datetime", vec![]),
+            ("datetime", vec![s("%Y")]),
+            ("account", vec![]),
+            ("hex", vec![]),
+            ("principal", vec![]),
+        ];
+        for (name, args) in valid {
+            assert_eq!(validate(&filter(name, args.clone())), Ok(()), "{} {:?}", name, args);
+        }
+
+        let invalid = [
+            ("upper", vec![s("x")]),
+            ("length", vec![Literal::Int(1)]),
+            ("truncate", vec![]),
+            ("truncate", vec![Literal::Int(0)]),
+            ("truncate", vec![s("10")]),
+            ("default", vec![]),
+            ("default", vec![s("a"), s("b")]),
+            ("join", vec![]),
+            ("join", vec![Literal::Int(1)]),
+            ("tokens", vec![]),
+            ("tokens", vec![Literal::Int(-1)]),
+            ("tokens", vec![Literal::Int(39)]),
+            ("tokens", vec![Literal::Int(8), Literal::Int(8)]),
+            ("datetime", vec![Literal::Int(1)]),
+            ("datetime", vec![s("%Y"), s("%m")]),
+            ("account", vec![s("x")]),
+            ("hex", vec![Literal::Int(1)]),
+            ("principal", vec![s("x")]),
+        ];
+        for (name, args) in invalid {
+            assert_eq!(
+                validate(&filter(name, args.clone())),
+                Err(format!("invalid arguments for filter '{}'", name)),
+                "{} {:?}", name, args
+            );
+        }
+
+        assert_eq!(validate(&filter("nope", vec![])), Err("unknown filter 'nope'".to_string()));
+    }
+
+    #[test]
+    fn applies_filters() {
+        let text = |value: Option<Value>| value.map(|v| to_text(&v));
+
+        assert_eq!(text(apply(&filter("truncate", vec![Literal::Int(3)]), Some(Value::Text("abcdef".to_string())))), Some("abc…".to_string()));
+        assert_eq!(text(apply(&filter("tokens", vec![Literal::Int(8), s("ICP")]), Some(Value::Nat64(1_234_500_000_000)))), Some("12,345 ICP".to_string()));
+        assert_eq!(text(apply(&filter("datetime", vec![]), Some(Value::Nat64(1_700_000_000_000_000_000)))), Some("2023-11-14 22:13:20 UTC".to_string()));
+        assert_eq!(text(apply(&filter("hex", vec![]), Some(Value::Blob(vec![0xde, 0xad].into())))), Some("dead".to_string()));
+        assert_eq!(text(apply(&filter("default", vec![s("-")]), None)), Some("-".to_string()));
+        assert_eq!(text(apply(&filter("upper", vec![]), None)), None);
+    }
+}
@@ -0,0 +1,237 @@
+use candid::Principal;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun",
+    "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"
+];
+
+pub fn format_tokens(
+    amount: &str,
+    decimals: usize,
+    symbol: Option<&str>
+) -> String {
+    let (sign, digits) = match amount.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", amount),
+    };
+
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (int_part, frac_part) = digits.split_at(digits.len() - decimals);
+
+    let mut int_grouped = String::new();
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            int_grouped.push(',');
+        }
+        int_grouped.push(c);
+    }
+
+    let frac_part = frac_part.trim_end_matches('0');
+    let mut text = if frac_part.is_empty() {
+        format!("{}{}", sign, int_grouped)
+    }
+    else {
+        format!("{}{}.{}", sign, int_grouped, frac_part)
+    };
+
+    if let Some(symbol) = symbol {
+        text.push(' ');
+        text.push_str(symbol);
+    }
+
+    text
+}
+
+pub fn format_datetime(
+    timestamp: u64,
+    fmt: &str
+) -> String {
+    // accept seconds, milliseconds, microseconds and nanoseconds
+    let secs = if timestamp < 100_000_000_000 {
+        timestamp
+    }
+    else if timestamp < 100_000_000_000_000 {
+        timestamp / 1_000
+    }
+    else if timestamp < 100_000_000_000_000_000 {
+        timestamp / 1_000_000
+    }
+    else {
+        timestamp / 1_000_000_000
+    };
+
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    let (hour, min, sec) = (rem / 3_600, (rem % 3_600) / 60, rem % 60);
+
+    let mut out = String::new();
+    let mut chars = fmt.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => out.push_str(&format!("{:04}", year)),
+            Some('y') => out.push_str(&format!("{:02}", year % 100)),
+            Some('m') => out.push_str(&format!("{:02}", month)),
+            Some('b') => out.push_str(MONTHS[(month - 1) as usize]),
+            Some('d') => out.push_str(&format!("{:02}", day)),
+            Some('e') => out.push_str(&day.to_string()),
+            Some('H') => out.push_str(&format!("{:02}", hour)),
+            Some('M') => out.push_str(&format!("{:02}", min)),
+            Some('S') => out.push_str(&format!("{:02}", sec)),
+            Some('s') => out.push_str(&secs.to_string()),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            },
+            None => out.push('%'),
+        }
+    }
+
+    out
+}
+
+pub fn format_account(
+    owner: &[u8],
+    subaccount: Option<&[u8]>
+) -> Option<String> {
+    let owner_text = Principal::try_from_slice(owner).ok()?.to_text();
+
+    let subaccount = match subaccount {
+        Some(sub) if sub.iter().any(|b| *b != 0) => sub,
+        _ => return Some(owner_text),
+    };
+
+    let mut bytes = owner.to_vec();
+    bytes.extend_from_slice(subaccount);
+    let checksum = base32(&crc32(&bytes).to_be_bytes());
+
+    let sub_hex = hex::encode(subaccount);
+    let sub_hex = sub_hex.trim_start_matches('0');
+
+    Some(format!("{}-{}.{}", owner_text, checksum, sub_hex))
+}
+
+pub fn format_principal(
+    bytes: &[u8]
+) -> Option<String> {
+    Principal::try_from_slice(bytes)
+        .ok()
+        .map(|p| p.to_text())
+}
+
+// days since 1970-01-01 to (year, month, day), see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(
+    days: i64
+) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+fn crc32(
+    bytes: &[u8]
+) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            }
+            else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn base32(
+    bytes: &[u8]
+) -> String {
+    let mut out = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1F) as usize] as char);
+        }
+    }
+
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1F) as usize] as char);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OWNER: &str = "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae";
+
+    #[test]
+    fn formats_accounts() {
+        let owner = Principal::from_text(OWNER).unwrap();
+        let owner = owner.as_slice();
+
+        // the example from the ICRC-1 standard
+        let sub = (1..=32).collect::<Vec<u8>>();
+        assert_eq!(
+            format_account(owner, Some(&sub)),
+            Some(format!("{}-dfxgiyy.102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20", OWNER))
+        );
+
+        // default subaccounts are omitted
+        assert_eq!(format_account(owner, None), Some(OWNER.to_string()));
+        assert_eq!(format_account(owner, Some(&[0; 32])), Some(OWNER.to_string()));
+
+        // a checksum depends on the subaccount
+        let mut sub = [0u8; 32];
+        sub[31] = 1;
+        let text = format_account(owner, Some(&sub)).unwrap();
+        assert!(text.starts_with(&format!("{}-", OWNER)));
+        assert!(text.ends_with(".1"));
+        assert_eq!(text.len(), OWNER.len() + 1 + 7 + 2);
+
+        assert_eq!(format_account(&[0xff; 40], None), None);
+    }
+
+    #[test]
+    fn formats_tokens() {
+        assert_eq!(format_tokens("0", 8, None), "0");
+        assert_eq!(format_tokens("1", 8, None), "0.00000001");
+        assert_eq!(format_tokens("123456789", 8, Some("ICP")), "1.23456789 ICP");
+        assert_eq!(format_tokens("1234567", 0, None), "1,234,567");
+        assert_eq!(format_tokens("-150", 2, None), "-1.5");
+    }
+
+    #[test]
+    fn formats_datetimes() {
+        // seconds, milliseconds, microseconds and nanoseconds
+        for timestamp in [951_782_400, 951_782_400_000, 951_782_400_000_000, 951_782_400_000_000_000] {
+            assert_eq!(format_datetime(timestamp, "%Y-%m-%d %H:%M:%S"), "2000-02-29 00:00:00");
+        }
+        assert_eq!(format_datetime(0, "%e %b %y %%"), "1 Jan 70 %");
+    }
+}
@@ -1 +1,7 @@
-pub mod scheduler;
\ No newline at end of file
+pub mod scheduler;
+pub mod template;
+pub mod filters;
+pub mod format;
+pub mod nat;
+pub mod condition;
+pub mod delivery;
@@ -0,0 +1,12 @@
+use candid::Nat;
+
+pub fn nat_to_u64(
+    value: &Nat
+) -> u64 {
+    let mut digits = value.0.iter_u64_digits();
+    match (digits.next(), digits.next()) {
+        (None, _) => 0,
+        (Some(n), None) => n,
+        _ => u64::MAX,
+    }
+}
@@ -44,6 +44,16 @@ impl<T> Scheduler<T>
         Ok(next_due)
     }
 
+    pub fn reschedule(
+        &mut self,
+        job_id: JobId,
+        job: T,
+        now: u64,
+    ) -> Result<bool, String> {
+        self.ordered.retain(|(_, id)| *id != job_id);
+        self.add_ex(job_id, job, now)
+    }
+
     pub fn add(
         &mut self,
         job: T,
@@ -111,6 +121,15 @@ impl<T> Scheduler<T>
         self.start_if_required(timer_cb);
     }
 
+    pub fn next_due(
+        &self,
+        job_id: JobId
+    ) -> Option<u64> {
+        self.ordered.iter()
+            .find(|(_, id)| *id == job_id)
+            .map(|(timestamp, _)| *timestamp)
+    }
+
     fn peek(
         &self
     ) -> Option<(u64, JobId)> {
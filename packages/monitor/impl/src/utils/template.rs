@@ -0,0 +1,698 @@
+use std::{collections::BTreeMap, iter::Peekable, str::Chars};
+use icrc_ledger_types::icrc::generic_value::Value;
+use crate::{
+    types::template::{Expr, Filter, Literal, Node, Template},
+    utils::filters
+};
+
+const MAX_EACH_ITEMS: usize = 100;
+const MAX_ITERATIONS: usize = 1_000; // across all the {#each} blocks, nested ones included
+const MAX_OUTPUT_LEN: usize = 8 * 1024; // in bytes
+const TRUNCATED: &str = "…";
+
+enum Token {
+    Text(String),
+    Tag(String, usize),
+}
+
+enum Tag {
+    Expr(Expr),
+    If(Expr),
+    Else,
+    EndIf,
+    Each(Expr, String),
+    EndEach,
+}
+
+type Block = (Vec<Node>, Option<(Tag, usize)>);
+
+#[derive(PartialEq)]
+enum Lexeme {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    Pipe,
+    LParen,
+    RParen,
+    Comma,
+}
+
+struct Scope<'a> {
+    root: &'a BTreeMap<String, Value>,
+    locals: Vec<(String, Value)>,
+}
+
+struct Output {
+    text: String,
+    iterations: usize,
+    truncated: bool,
+}
+
+impl Output {
+    // appends the text while the budget lasts, returning false once it's exhausted
+    fn push(
+        &mut self,
+        text: &str
+    ) -> bool {
+        if self.truncated {
+            return false;
+        }
+
+        let left = MAX_OUTPUT_LEN.saturating_sub(self.text.len());
+        if text.len() <= left {
+            self.text.push_str(text);
+            return true;
+        }
+
+        self.text.push_str(&text[..Self::floor_char_boundary(text, left)]);
+        self.truncate();
+
+        false
+    }
+
+    fn iterate(
+        &mut self
+    ) -> bool {
+        if self.iterations >= MAX_ITERATIONS {
+            self.truncate();
+            return false;
+        }
+
+        self.iterations += 1;
+        !self.truncated
+    }
+
+    // ends the text with the marker, cutting it to keep both within the budget
+    fn truncate(
+        &mut self
+    ) {
+        if self.truncated {
+            return;
+        }
+
+        let len = MAX_OUTPUT_LEN - TRUNCATED.len();
+        if self.text.len() > len {
+            let end = Self::floor_char_boundary(&self.text, len);
+            self.text.truncate(end);
+        }
+        self.text.push_str(TRUNCATED);
+        self.truncated = true;
+    }
+
+    fn floor_char_boundary(
+        text: &str,
+        mut end: usize
+    ) -> usize {
+        end = end.min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        end
+    }
+}
+
+impl Template {
+    pub fn parse(
+        src: &str
+    ) -> Result<Self, String> {
+        let mut tokens = Self::tokenize(src)?.into_iter();
+
+        let (nodes, end) = Self::parse_nodes(&mut tokens)?;
+        if let Some((_, pos)) = end {
+            return Err(format!("Template error at position {}: unexpected closing tag", pos));
+        }
+
+        Ok(Self {
+            nodes
+        })
+    }
+
+    pub fn render(
+        &self,
+        event: &BTreeMap<String, Value>
+    ) -> String {
+        let mut out = Output {
+            text: String::new(),
+            iterations: 0,
+            truncated: false,
+        };
+        let mut scope = Scope {
+            root: event,
+            locals: vec![],
+        };
+
+        Self::render_nodes(&self.nodes, &mut scope, &mut out);
+
+        out.text
+    }
+
+    fn tokenize(
+        src: &str
+    ) -> Result<Vec<Token>, String> {
+        let chars = src.chars().collect::<Vec<_>>();
+        let mut tokens = vec![];
+        let mut text = String::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '{' if chars.get(i + 1) == Some(&'{') => {
+                    text.push('{');
+                    i += 2;
+                },
+                '}' if chars.get(i + 1) == Some(&'}') => {
+                    text.push('}');
+                    i += 2;
+                },
+                '{' => {
+                    let start = i;
+                    let mut tag = String::new();
+                    let mut quoted = false;
+                    let mut closed = false;
+                    i += 1;
+
+                    while i < chars.len() {
+                        let c = chars[i];
+                        i += 1;
+                        if quoted {
+                            if c == '\\' && i < chars.len() {
+                                tag.push(c);
+                                tag.push(chars[i]);
+                                i += 1;
+                                continue;
+                            }
+                            else if c == '"' {
+                                quoted = false;
+                            }
+                        }
+                        else if c == '"' {
+                            quoted = true;
+                        }
+                        else if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        else if c == '{' {
+                            return Err(format!("Template error at position {}: unexpected '{{' inside tag", i - 1));
+                        }
+                        tag.push(c);
+                    }
+
+                    if !closed {
+                        return Err(format!("Template error at position {}: unclosed '{{'", start));
+                    }
+
+                    if !text.is_empty() {
+                        tokens.push(Token::Text(std::mem::take(&mut text)));
+                    }
+                    tokens.push(Token::Tag(tag, start));
+                },
+                c => {
+                    text.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+
+        Ok(tokens)
+    }
+
+    fn parse_nodes(
+        tokens: &mut std::vec::IntoIter<Token>
+    ) -> Result<Block, String> {
+        let mut nodes = vec![];
+
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::Text(text) => {
+                    nodes.push(Node::Text(text));
+                },
+                Token::Tag(src, pos) => {
+                    match Self::parse_tag(&src)
+                        .map_err(|e| format!("Template error at position {}: {}", pos, e))? {
+                        Tag::Expr(expr) => {
+                            nodes.push(Node::Expr(expr));
+                        },
+                        Tag::If(cond) => {
+                            let (then, end) = Self::parse_nodes(tokens)?;
+                            let otherwise = match end {
+                                Some((Tag::EndIf, _)) => {
+                                    vec![]
+                                },
+                                Some((Tag::Else, _)) => {
+                                    match Self::parse_nodes(tokens)? {
+                                        (otherwise, Some((Tag::EndIf, _))) => otherwise,
+                                        _ => return Err(format!("Template error at position {}: {{#if}} without {{/if}}", pos)),
+                                    }
+                                },
+                                _ => {
+                                    return Err(format!("Template error at position {}: {{#if}} without {{/if}}", pos));
+                                }
+                            };
+
+                            nodes.push(Node::If {
+                                cond,
+                                then,
+                                otherwise
+                            });
+                        },
+                        Tag::Each(expr, alias) => {
+                            match Self::parse_nodes(tokens)? {
+                                (body, Some((Tag::EndEach, _))) => {
+                                    nodes.push(Node::Each {
+                                        expr,
+                                        alias,
+                                        body
+                                    });
+                                },
+                                _ => {
+                                    return Err(format!("Template error at position {}: {{#each}} without {{/each}}", pos));
+                                }
+                            }
+                        },
+                        end => {
+                            return Ok((nodes, Some((end, pos))));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok((nodes, None))
+    }
+
+    fn parse_tag(
+        src: &str
+    ) -> Result<Tag, String> {
+        let src = src.trim();
+
+        if let Some(rest) = src.strip_prefix('#') {
+            let (keyword, rest) = rest.split_once(char::is_whitespace)
+                .unwrap_or((rest, ""));
+
+            match keyword {
+                "if" => {
+                    let mut lexemes = Self::lex(rest)?.into_iter().peekable();
+                    let cond = Self::parse_expr(&mut lexemes)?;
+                    if lexemes.next().is_some() {
+                        return Err("unexpected input after {#if} condition".to_string());
+                    }
+                    Ok(Tag::If(cond))
+                },
+                "else" if rest.trim().is_empty() => {
+                    Ok(Tag::Else)
+                },
+                "each" => {
+                    let mut lexemes = Self::lex(rest)?.into_iter().peekable();
+                    let expr = Self::parse_expr(&mut lexemes)?;
+                    match (lexemes.next(), lexemes.next(), lexemes.next()) {
+                        (Some(Lexeme::Ident(kw)), Some(Lexeme::Ident(alias)), None)
+                            if kw == "as" && !alias.contains('.') && !alias.starts_with('@') => {
+                            Ok(Tag::Each(expr, alias))
+                        },
+                        _ => {
+                            Err("expected {#each <path> as <name>}".to_string())
+                        }
+                    }
+                },
+                _ => {
+                    Err(format!("unknown block '#{}'", keyword))
+                }
+            }
+        }
+        else if let Some(rest) = src.strip_prefix('/') {
+            match rest.trim() {
+                "if" => Ok(Tag::EndIf),
+                "each" => Ok(Tag::EndEach),
+                other => Err(format!("unknown closing block '/{}'", other)),
+            }
+        }
+        else {
+            let mut lexemes = Self::lex(src)?.into_iter().peekable();
+            let expr = Self::parse_expr(&mut lexemes)?;
+            if lexemes.next().is_some() {
+                return Err("unexpected input after expression".to_string());
+            }
+            Ok(Tag::Expr(expr))
+        }
+    }
+
+    fn parse_expr<I>(
+        lexemes: &mut Peekable<I>
+    ) -> Result<Expr, String>
+        where I: Iterator<Item = Lexeme> {
+        let path = match lexemes.next() {
+            Some(Lexeme::Ident(ident)) => {
+                Self::parse_path(&ident)?
+            },
+            _ => {
+                return Err("expected a field name".to_string());
+            }
+        };
+
+        let mut filters = vec![];
+        while lexemes.next_if_eq(&Lexeme::Pipe).is_some() {
+            let name = match lexemes.next() {
+                Some(Lexeme::Ident(name)) => name,
+                _ => return Err("expected a filter name after '|'".to_string()),
+            };
+
+            let mut args = vec![];
+            if lexemes.next_if_eq(&Lexeme::LParen).is_some() {
+                loop {
+                    match lexemes.next() {
+                        Some(Lexeme::Str(s)) => args.push(Literal::Str(s)),
+                        Some(Lexeme::Int(n)) => args.push(Literal::Int(n)),
+                        Some(Lexeme::RParen) if args.is_empty() => break,
+                        _ => return Err(format!("invalid arguments for filter '{}'", name)),
+                    }
+                    match lexemes.next() {
+                        Some(Lexeme::Comma) => continue,
+                        Some(Lexeme::RParen) => break,
+                        _ => return Err(format!("expected ',' or ')' in filter '{}'", name)),
+                    }
+                }
+            }
+
+            let filter = Filter {
+                name,
+                args
+            };
+            filters::validate(&filter)?;
+            filters.push(filter);
+        }
+
+        Ok(Expr {
+            path,
+            filters
+        })
+    }
+
+    fn parse_path(
+        ident: &str
+    ) -> Result<Vec<String>, String> {
+        let path = ident.split('.')
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        if path.iter().any(|s| s.is_empty()) {
+            return Err(format!("invalid field path '{}'", ident));
+        }
+
+        Ok(path)
+    }
+
+    fn lex(
+        src: &str
+    ) -> Result<Vec<Lexeme>, String> {
+        let mut lexemes = vec![];
+        let mut chars = src.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            match c {
+                c if c.is_whitespace() => {
+                    chars.next();
+                },
+                '|' => {
+                    chars.next();
+                    lexemes.push(Lexeme::Pipe);
+                },
+                '(' => {
+                    chars.next();
+                    lexemes.push(Lexeme::LParen);
+                },
+                ')' => {
+                    chars.next();
+                    lexemes.push(Lexeme::RParen);
+                },
+                ',' => {
+                    chars.next();
+                    lexemes.push(Lexeme::Comma);
+                },
+                '"' => {
+                    chars.next();
+                    lexemes.push(Lexeme::Str(Self::lex_string(&mut chars)?));
+                },
+                c if c.is_ascii_digit() || c == '-' => {
+                    let num = Self::take_while(&mut chars, |c| c.is_ascii_digit() || c == '-' || c == '_');
+                    let num = num.replace('_', "")
+                        .parse::<i64>()
+                        .map_err(|_| format!("invalid number '{}'", num))?;
+                    lexemes.push(Lexeme::Int(num));
+                },
+                c if c.is_alphanumeric() || c == '_' || c == '@' => {
+                    let ident = Self::take_while(&mut chars, |c| c.is_alphanumeric() || c == '_' || c == '.' || c == '@');
+                    lexemes.push(Lexeme::Ident(ident));
+                },
+                c => {
+                    return Err(format!("unexpected character '{}'", c));
+                }
+            }
+        }
+
+        Ok(lexemes)
+    }
+
+    fn lex_string(
+        chars: &mut Peekable<Chars>
+    ) -> Result<String, String> {
+        let mut s = String::new();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(s),
+                '\\' => match chars.next() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some(c) => s.push(c),
+                    None => break,
+                },
+                c => s.push(c),
+            }
+        }
+
+        Err("unterminated string".to_string())
+    }
+
+    fn take_while<F>(
+        chars: &mut Peekable<Chars>,
+        pred: F
+    ) -> String
+        where F: Fn(char) -> bool {
+        let mut s = String::new();
+        while let Some(c) = chars.next_if(|c| pred(*c)) {
+            s.push(c);
+        }
+        s
+    }
+
+    fn render_nodes(
+        nodes: &[Node],
+        scope: &mut Scope,
+        out: &mut Output
+    ) -> bool {
+        for node in nodes {
+            let more = match node {
+                Node::Text(text) => {
+                    out.push(text)
+                },
+                Node::Expr(expr) => {
+                    match Self::eval(expr, scope) {
+                        Some(value) => out.push(&filters::to_text(&value)),
+                        None => true,
+                    }
+                },
+                Node::If { cond, then, otherwise } => {
+                    if filters::is_truthy(&Self::eval(cond, scope)) {
+                        Self::render_nodes(then, scope, out)
+                    }
+                    else {
+                        Self::render_nodes(otherwise, scope, out)
+                    }
+                },
+                Node::Each { expr, alias, body } => {
+                    let items = match Self::eval(expr, scope) {
+                        Some(Value::Array(items)) => items,
+                        Some(Value::Map(map)) => map.into_values().collect(),
+                        Some(other) => vec![other],
+                        None => vec![],
+                    };
+
+                    let mut more = true;
+                    for (index, item) in items.into_iter().take(MAX_EACH_ITEMS).enumerate() {
+                        if !out.iterate() {
+                            more = false;
+                            break;
+                        }
+
+                        scope.locals.push(("@index".to_string(), Value::Nat64(index as _)));
+                        scope.locals.push((alias.clone(), item));
+                        more = Self::render_nodes(body, scope, out);
+                        scope.locals.pop();
+                        scope.locals.pop();
+
+                        if !more {
+                            break;
+                        }
+                    }
+                    more
+                },
+            };
+
+            if !more {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn eval(
+        expr: &Expr,
+        scope: &Scope
+    ) -> Option<Value> {
+        let value = Self::resolve(&expr.path, scope);
+
+        expr.filters.iter()
+            .fold(value, |value, filter| filters::apply(filter, value))
+    }
+
+    fn resolve(
+        path: &[String],
+        scope: &Scope
+    ) -> Option<Value> {
+        let (head, tail) = path.split_first()?;
+
+        let mut value = scope.locals.iter()
+            .rev()
+            .find(|(name, _)| name == head)
+            .map(|(_, value)| value)
+            .or_else(|| scope.root.get(head))?;
+
+        for key in tail {
+            value = match value {
+                Value::Map(map) => map.get(key)?,
+                Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+
+        Some(value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Nat;
+    use super::*;
+
+    fn event(
+    ) -> BTreeMap<String, Value> {
+        BTreeMap::from([
+            ("name".to_string(), Value::Text("alice".to_string())),
+            ("amount".to_string(), Value::Nat(Nat::from(123_456_789u64))),
+            ("tx".to_string(), Value::Map(BTreeMap::from([
+                ("to".to_string(), Value::Text("bob".to_string())),
+            ]))),
+            ("items".to_string(), Value::Array(vec![
+                Value::Text("a".to_string()),
+                Value::Text("b".to_string()),
+                Value::Text("c".to_string()),
+            ])),
+        ])
+    }
+
+    fn render(
+        src: &str
+    ) -> String {
+        Template::parse(src).unwrap().render(&event())
+    }
+
+    #[test]
+    fn renders_paths_and_filters() {
+        assert_eq!(render("hi {name}"), "hi alice");
+        assert_eq!(render("{tx.to | upper}"), "BOB");
+        assert_eq!(render("{items.1}"), "b");
+        assert_eq!(render("{amount | tokens(8, \"ICP\")}"), "1.23456789 ICP");
+        assert_eq!(render("[{missing}]"), "[]");
+        assert_eq!(render("{missing | default(\"none\")}"), "none");
+        assert_eq!(render("{{literal}}"), "{literal}");
+    }
+
+    #[test]
+    fn renders_blocks() {
+        assert_eq!(render("{#if name}yes{#else}no{/if}"), "yes");
+        assert_eq!(render("{#if missing}yes{#else}no{/if}"), "no");
+        assert_eq!(render("{#each items as item}{@index}={item};{/each}"), "0=a;1=b;2=c;");
+        assert_eq!(render("{#each items as x}{#each items as y}{x}{y} {/each}{/each}"), "aa ab ac ba bb bc ca cb cc ");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        let cases = [
+            ("{name", "unclosed '{'"),
+            ("{#if name}yes", "{#if} without {/if}"),
+            ("{#each items}{/each}", "expected {#each <path> as <name>}"),
+            ("{#each items as x}", "{#each} without {/each}"),
+            ("{/if}", "unexpected closing tag"),
+            ("{/while}", "unknown closing block '/while'"),
+            ("{#while name}", "unknown block '#while'"),
+            ("{name | nope}", "unknown filter 'nope'"),
+            ("{name | truncate}", "invalid arguments for filter 'truncate'"),
+            ("{name | truncate(1, 2}", "expected ',' or ')' in filter 'truncate'"),
+            ("{a..b}", "invalid field path 'a..b'"),
+            ("{name \"x}", "unclosed '{'"),
+            ("{name extra}", "unexpected input after expression"),
+        ];
+
+        for (src, err) in cases {
+            match Template::parse(src) {
+                Ok(_) => panic!("'{}' should fail", src),
+                Err(e) => assert!(e.contains(err), "'{}': '{}' doesn't contain '{}'", src, e, err),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        let err = Template::parse("abc {name | nope}").err().unwrap();
+        assert!(err.starts_with("Template error at position 4:"), "{}", err);
+    }
+
+    #[test]
+    fn truncates_long_output() {
+        let text = render(&"x".repeat(MAX_OUTPUT_LEN + 100));
+        assert_eq!(text.len(), MAX_OUTPUT_LEN);
+        assert!(text.ends_with(TRUNCATED));
+
+        // never splits a multi-byte char
+        let text = render(&"é".repeat(MAX_OUTPUT_LEN));
+        assert!(text.len() <= MAX_OUTPUT_LEN);
+        assert!(text.ends_with(TRUNCATED));
+    }
+
+    #[test]
+    fn caps_loop_iterations() {
+        let items = Value::Array((0..MAX_EACH_ITEMS).map(|i| Value::Nat64(i as _)).collect());
+        let event = BTreeMap::from([("items".to_string(), items)]);
+
+        // 100 outer * (1 + 100 inner) iterations exceed the cap
+        let template = Template::parse("{#each items as x}{#each items as y}.{/each}{/each}").unwrap();
+        let text = template.render(&event);
+
+        assert!(text.ends_with(TRUNCATED));
+        assert!(text.matches('.').count() < MAX_ITERATIONS);
+
+        // the marker stays within the budget, also when the output is almost full
+        let filler = "x".repeat(MAX_OUTPUT_LEN - 1);
+        let template = Template::parse(&format!("{}{{#each items as x}}{{#each items as y}}{{/each}}{{/each}}", filler)).unwrap();
+        let text = template.render(&event);
+
+        assert!(text.len() <= MAX_OUTPUT_LEN);
+        assert!(text.ends_with(TRUNCATED));
+    }
+}